@@ -232,19 +232,20 @@ impl RefreshDaemon {
         // - If the app is not installed, we need to reinstall
         // - If the app is installed and identity is not new, we can just update profiles
         let needs_reinstall = device.is_mac || identity_is_new || !is_installed;
+        let mut store = store.clone();
 
         if needs_reinstall {
-            self.resign_and_reinstall(app, device, &session, team_id)
+            self.resign_and_reinstall(app, device, &session, team_id, &mut store)
                 .await?;
         } else {
             log::info!(
                 "Certificate exists and app is installed, updating provisioning profiles..."
             );
-            self.update_provisioning_profiles(app, device, &session, team_id)
+            self.update_provisioning_profiles(app, device, &session, team_id, &mut store)
                 .await?;
         }
 
-        self.update_refresh_schedule(store, refresh_device, app)
+        self.update_refresh_schedule(&mut store, refresh_device, app)
             .await?;
 
         log::info!("Successfully refreshed app at {:?}", app.path);
@@ -268,6 +269,7 @@ impl RefreshDaemon {
         device: &Device,
         session: &DeveloperSession,
         team_id: &str,
+        store: &mut AccountStore,
     ) -> Result<(), String> {
         let team_id_string = team_id.to_string();
         session
@@ -296,10 +298,12 @@ impl RefreshDaemon {
 
         let mut signer = Signer::new(Some(signing_identity), options);
 
+        Self::register_bundle(&mut signer, &bundle, session, team_id, store).await?;
+
         signer
-            .register_bundle(&bundle, session, &team_id.to_string(), true)
+            .ensure_profiles_compatible(&bundle, session, &team_id_string, Some(device))
             .await
-            .map_err(|e| format!("Failed to register bundle: {}", e))?;
+            .map_err(|e| format!("Incompatible provisioning profile: {}", e))?;
 
         signer
             .sign_bundle(&bundle)
@@ -326,6 +330,7 @@ impl RefreshDaemon {
         device: &Device,
         session: &DeveloperSession,
         team_id: &str,
+        store: &mut AccountStore,
     ) -> Result<(), String> {
         let bundle =
             Bundle::new(app.path.clone()).map_err(|e| format!("Failed to create bundle: {}", e))?;
@@ -337,10 +342,12 @@ impl RefreshDaemon {
 
         let mut signer = Signer::new(None, options);
 
+        Self::register_bundle(&mut signer, &bundle, session, team_id, store).await?;
+
         signer
-            .register_bundle(&bundle, session, &team_id.to_string(), true)
+            .ensure_profiles_compatible(&bundle, session, &team_id.to_string(), Some(device))
             .await
-            .map_err(|e| format!("Failed to register bundle: {}", e))?;
+            .map_err(|e| format!("Incompatible provisioning profile: {}", e))?;
 
         for provision in &signer.provisioning_files {
             device
@@ -352,9 +359,38 @@ impl RefreshDaemon {
         Ok(())
     }
 
+    // Registers App IDs like a manual install, so the free account quota stays accurate
+    async fn register_bundle(
+        signer: &mut Signer,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &str,
+        store: &mut AccountStore,
+    ) -> Result<(), String> {
+        let team_id = team_id.to_string();
+
+        let quota = signer
+            .ensure_app_id_quota(bundle, session, &team_id, store)
+            .await
+            .map_err(|e| format!("Failed to check App ID quota: {}", e))?;
+        if let Some(quota) = quota {
+            log::info!("App ID quota: {}", quota);
+        }
+
+        // App IDs created before a failure still count towards the quota
+        let registered = signer
+            .register_bundle(bundle, session, &team_id, true)
+            .await;
+        store
+            .add_app_ids_sync(signer.registered_app_ids.clone())
+            .map_err(|e| format!("Failed to record App IDs: {}", e))?;
+
+        registered.map_err(|e| format!("Failed to register bundle: {}", e))
+    }
+
     async fn update_refresh_schedule(
         &self,
-        store: &AccountStore,
+        store: &mut AccountStore,
         refresh_device: &RefreshDevice,
         app: &plume_store::RefreshApp,
     ) -> Result<(), String> {
@@ -373,7 +409,6 @@ impl RefreshDaemon {
             .unwrap_or_else(|_| Utc::now() + chrono::Duration::days(4));
         let scheduled_refresh = scheduled_refresh - chrono::Duration::days(3);
 
-        let mut store = store.clone();
         let mut updated_device = refresh_device.clone();
 
         if let Some(existing_app) = updated_device.apps.iter_mut().find(|a| a.path == app.path) {
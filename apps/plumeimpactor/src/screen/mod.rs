@@ -358,9 +358,10 @@ impl Impactor {
                     if let general::Message::NavigateToInstaller(package) = msg {
                         let mut options = SignerOptions::default();
                         package.load_into_signer_options(&mut options);
-                        self.current_screen = ImpactorScreen::Installer(
-                            package::PackageScreen::new(Some(package), options),
-                        );
+                        let (screen, scan) = package::PackageScreen::new(Some(package), options);
+                        self.current_screen = ImpactorScreen::Installer(screen);
+
+                        return Task::batch(vec![task, scan.map(Message::InstallerScreen)]);
                     } else if let general::Message::NavigateToUtilities = msg {
                         self.current_screen = ImpactorScreen::Utilities(
                             utilties::UtilitiesScreen::new(self.selected_device.clone()),
@@ -521,6 +522,51 @@ impl Impactor {
 
                             self.start_installation_task()
                         }
+                        package::Message::CheckEntitlements => {
+                            let (Some(package), Some(account)) = (
+                                screen.selected_package.clone(),
+                                self.account_store
+                                    .as_ref()
+                                    .and_then(|s| s.selected_account())
+                                    .cloned(),
+                            ) else {
+                                return screen
+                                    .update(package::Message::EntitlementsLoaded(Err(
+                                        "No account selected".to_string(),
+                                    )))
+                                    .map(Message::InstallerScreen);
+                            };
+                            let options = screen.options.clone();
+
+                            let task = Task::perform(
+                                async move {
+                                    let (tx, rx) = std::sync::mpsc::channel();
+
+                                    std::thread::spawn(move || {
+                                        let rt = tokio::runtime::Runtime::new().unwrap();
+                                        let result = rt.block_on(async move {
+                                            crate::subscriptions::fetch_entitlement_report(
+                                                &account, &package, &options,
+                                            )
+                                            .await
+                                        });
+                                        let _ = tx.send(result);
+                                    });
+
+                                    rx.recv().unwrap_or_else(|e| Err(e.to_string()))
+                                },
+                                |report| {
+                                    Message::InstallerScreen(package::Message::EntitlementsLoaded(
+                                        report,
+                                    ))
+                                },
+                            );
+
+                            Task::batch(vec![
+                                screen.update(msg).map(Message::InstallerScreen),
+                                task,
+                            ])
+                        }
                         _ => screen.update(msg).map(Message::InstallerScreen),
                     }
                 } else {
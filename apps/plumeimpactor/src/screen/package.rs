@@ -2,7 +2,11 @@ use iced::widget::{
     button, checkbox, column, container, pick_list, row, scrollable, text, text_input,
 };
 use iced::{Alignment, Center, Element, Fill, Task};
-use plume_utils::{Package, PlistInfoTrait, SignerInstallMode, SignerMode, SignerOptions};
+use plume_utils::{
+    EntitlementReport, EntitlementStatus, Package, PlistInfoTrait, RemovableBundle,
+    RemovableBundleKind, SignerBundleRemoval, SignerCompression, SignerHookRuntime,
+    SignerInstallMode, SignerMode, SignerOptions, SignerTweak, SignerTweakTarget,
+};
 
 use crate::appearance;
 
@@ -19,12 +23,32 @@ pub enum Message {
     ToggleSingleProfile(bool),
     ToggleLiquidGlass(bool),
     ToggleRefresh(bool),
+    ToggleReuseAppIds(bool),
     ToggleElleKit(bool),
+    ToggleRemoveUrlSchemes(bool),
+    ToggleRemoveSupportedDevices(bool),
+    ToggleRemoveQueriesSchemes(bool),
+    ToggleRemoveRequiredCapabilities(bool),
+    ToggleThinForDevice(bool),
+    ToggleStripSymbols(bool),
     UpdateSignerMode(SignerMode),
     UpdateInstallMode(SignerInstallMode),
+    UpdateCompression(SignerCompression),
     AddTweak,
     AddBundle,
+    SelectTweakRepository,
+    ClearTweakRepository,
+    SelectHookRuntime,
+    ResetHookRuntime,
     RemoveTweak(usize),
+    UpdateTweakTarget(usize, SignerTweakTarget),
+    ToggleTweakRequired(usize, bool),
+    ToggleRemoveAllBundles(bool),
+    ToggleRemoveBundle(String, bool),
+    ToggleAllowEncrypted(bool),
+    EncryptedBinariesLoaded(Result<Vec<String>, String>),
+    CheckEntitlements,
+    EntitlementsLoaded(Result<EntitlementReport, String>),
     Back,
     RequestInstallation,
 }
@@ -33,14 +57,60 @@ pub enum Message {
 pub struct PackageScreen {
     pub selected_package: Option<Package>,
     pub options: SignerOptions,
+    removable_bundles: Vec<RemovableBundle>,
+    encrypted_binaries: Vec<String>,
+    encryption_error: Option<String>,
+    checking_encryption: bool,
+    allow_encrypted: bool,
+    entitlement_report: Option<Result<EntitlementReport, String>>,
+    checking_entitlements: bool,
 }
 
 impl PackageScreen {
-    pub fn new(package: Option<Package>, options: SignerOptions) -> Self {
-        Self {
-            selected_package: package,
-            options,
-        }
+    pub fn new(package: Option<Package>, options: SignerOptions) -> (Self, Task<Message>) {
+        let removable_bundles = package
+            .as_ref()
+            .and_then(|p| p.get_removable_bundles().ok())
+            .unwrap_or_default();
+
+        // Reading every binary of a large app takes a while, keep it off the UI thread
+        let task = match package.clone() {
+            Some(package) => {
+                let (tx, rx) = tokio::sync::oneshot::channel();
+
+                std::thread::spawn(move || {
+                    let _ = tx.send(
+                        package
+                            .get_encrypted_binaries()
+                            .map_err(|e| format!("Failed to check for encrypted binaries: {}", e)),
+                    );
+                });
+
+                Task::perform(
+                    async move {
+                        rx.await
+                            .unwrap_or_else(|_| Err("Failed to receive result".to_string()))
+                    },
+                    Message::EncryptedBinariesLoaded,
+                )
+            }
+            None => Task::none(),
+        };
+
+        (
+            Self {
+                checking_encryption: package.is_some(),
+                selected_package: package,
+                options,
+                removable_bundles,
+                encrypted_binaries: Vec::new(),
+                encryption_error: None,
+                allow_encrypted: false,
+                entitlement_report: None,
+                checking_entitlements: false,
+            },
+            task,
+        )
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
@@ -119,10 +189,38 @@ impl PackageScreen {
                 self.options.refresh = value;
                 Task::none()
             }
+            Message::ToggleReuseAppIds(value) => {
+                self.options.reuse_stale_app_ids = value;
+                Task::none()
+            }
             Message::ToggleElleKit(value) => {
                 self.options.features.support_ellekit = value;
                 Task::none()
             }
+            Message::ToggleRemoveUrlSchemes(value) => {
+                self.options.features.remove_url_schemes = value;
+                Task::none()
+            }
+            Message::ToggleRemoveSupportedDevices(value) => {
+                self.options.features.remove_supported_devices = value;
+                Task::none()
+            }
+            Message::ToggleRemoveQueriesSchemes(value) => {
+                self.options.features.remove_queries_schemes = value;
+                Task::none()
+            }
+            Message::ToggleRemoveRequiredCapabilities(value) => {
+                self.options.features.remove_required_capabilities = value;
+                Task::none()
+            }
+            Message::ToggleThinForDevice(value) => {
+                self.options.features.thin_for_device = value;
+                Task::none()
+            }
+            Message::ToggleStripSymbols(value) => {
+                self.options.features.strip_symbols = value;
+                Task::none()
+            }
             Message::UpdateSignerMode(mode) => {
                 self.options.mode = mode;
                 Task::none()
@@ -131,6 +229,10 @@ impl PackageScreen {
                 self.options.install_mode = mode;
                 Task::none()
             }
+            Message::UpdateCompression(compression) => {
+                self.options.compression = compression;
+                Task::none()
+            }
             Message::AddTweak => {
                 let path = rfd::FileDialog::new()
                     .add_filter("Tweak files", &["deb", "dylib"])
@@ -139,8 +241,8 @@ impl PackageScreen {
 
                 if let Some(path) = path {
                     match &mut self.options.tweaks {
-                        Some(vec) => vec.push(path),
-                        None => self.options.tweaks = Some(vec![path]),
+                        Some(vec) => vec.push(SignerTweak::new(path)),
+                        None => self.options.tweaks = Some(vec![SignerTweak::new(path)]),
                     }
                 }
 
@@ -155,8 +257,8 @@ impl PackageScreen {
                     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
                         if ["framework", "bundle", "appex"].contains(&ext) {
                             match &mut self.options.tweaks {
-                                Some(vec) => vec.push(path),
-                                None => self.options.tweaks = Some(vec![path]),
+                                Some(vec) => vec.push(SignerTweak::new(path)),
+                                None => self.options.tweaks = Some(vec![SignerTweak::new(path)]),
                             }
                         }
                     }
@@ -164,6 +266,37 @@ impl PackageScreen {
 
                 Task::none()
             }
+            Message::SelectTweakRepository => {
+                let path = rfd::FileDialog::new()
+                    .set_title("Select Folder of Tweak Dependencies")
+                    .pick_folder();
+
+                if let Some(path) = path {
+                    self.options.tweak_repository = Some(path);
+                }
+
+                Task::none()
+            }
+            Message::ClearTweakRepository => {
+                self.options.tweak_repository = None;
+                Task::none()
+            }
+            Message::SelectHookRuntime => {
+                let path = rfd::FileDialog::new()
+                    .add_filter("Hooking runtime", &["deb", "dylib"])
+                    .set_title("Select Hooking Runtime")
+                    .pick_file();
+
+                if let Some(path) = path {
+                    self.options.hook_runtime = SignerHookRuntime::Custom(path);
+                }
+
+                Task::none()
+            }
+            Message::ResetHookRuntime => {
+                self.options.hook_runtime = SignerHookRuntime::default();
+                Task::none()
+            }
             Message::RemoveTweak(index) => {
                 if let Some(tweaks) = &mut self.options.tweaks {
                     if index < tweaks.len() {
@@ -172,6 +305,82 @@ impl PackageScreen {
                 }
                 Task::none()
             }
+            Message::UpdateTweakTarget(index, target) => {
+                if let Some(tweak) = self
+                    .options
+                    .tweaks
+                    .as_mut()
+                    .and_then(|tweaks| tweaks.get_mut(index))
+                {
+                    tweak.target = target;
+                }
+                Task::none()
+            }
+            Message::ToggleTweakRequired(index, value) => {
+                if let Some(tweak) = self
+                    .options
+                    .tweaks
+                    .as_mut()
+                    .and_then(|tweaks| tweaks.get_mut(index))
+                {
+                    tweak.weak = !value;
+                }
+                Task::none()
+            }
+            Message::ToggleRemoveAllBundles(value) => {
+                self.options.removed_bundles = if value {
+                    SignerBundleRemoval::RemoveAll
+                } else {
+                    SignerBundleRemoval::Keep
+                };
+                Task::none()
+            }
+            Message::ToggleRemoveBundle(path, value) => {
+                let mut selected = self
+                    .removable_bundles
+                    .iter()
+                    .filter(|b| self.options.removed_bundles.should_remove(&b.path))
+                    .map(|b| b.path.clone())
+                    .collect::<Vec<_>>();
+
+                if value {
+                    if !selected.contains(&path) {
+                        selected.push(path);
+                    }
+                } else {
+                    selected.retain(|p| *p != path);
+                }
+
+                self.options.removed_bundles = if selected.is_empty() {
+                    SignerBundleRemoval::Keep
+                } else if selected.len() == self.removable_bundles.len() {
+                    SignerBundleRemoval::RemoveAll
+                } else {
+                    SignerBundleRemoval::Remove(selected)
+                };
+                Task::none()
+            }
+            Message::ToggleAllowEncrypted(value) => {
+                self.allow_encrypted = value;
+                Task::none()
+            }
+            Message::CheckEntitlements => {
+                self.checking_entitlements = true;
+                Task::none()
+            }
+            Message::EntitlementsLoaded(report) => {
+                self.entitlement_report = Some(report);
+                self.checking_entitlements = false;
+                Task::none()
+            }
+            Message::EncryptedBinariesLoaded(result) => {
+                match result {
+                    Ok(binaries) => self.encrypted_binaries = binaries,
+                    Err(e) => self.encryption_error = Some(e),
+                }
+                self.checking_encryption = false;
+                Task::none()
+            }
             _ => Task::none(),
         }
     }
@@ -213,6 +422,7 @@ impl PackageScreen {
         let pkg_ver = pkg.get_version().unwrap_or_default();
 
         column![
+            self.view_encryption_warning(),
             text("Name:").size(12),
             text_input(
                 "App name",
@@ -243,8 +453,20 @@ impl PackageScreen {
                 button(appearance::icon_text(appearance::PLUS, "Add Bundle", None))
                     .on_press(Message::AddBundle)
                     .style(appearance::s_button),
+                button(appearance::icon_text(
+                    appearance::PLUS,
+                    "Dependencies",
+                    None
+                ))
+                .on_press(Message::SelectTweakRepository)
+                .style(appearance::s_button),
             ]
             .spacing(8),
+            self.view_tweak_repository(),
+            text("Remove Extensions:").size(12),
+            self.view_removable_bundles(),
+            text("Entitlements:").size(12),
+            self.view_entitlement_report(),
         ]
         .spacing(8)
         .width(Fill)
@@ -269,6 +491,19 @@ impl PackageScreen {
             checkbox(self.options.features.support_pro_motion)
                 .label("Force Pro Motion")
                 .on_toggle(Message::ToggleProMotion),
+            text("Info.plist:").size(12),
+            checkbox(self.options.features.remove_url_schemes)
+                .label("Remove URL Schemes")
+                .on_toggle(Message::ToggleRemoveUrlSchemes),
+            checkbox(self.options.features.remove_supported_devices)
+                .label("Remove Supported Devices")
+                .on_toggle(Message::ToggleRemoveSupportedDevices),
+            checkbox(self.options.features.remove_queries_schemes)
+                .label("Remove Queried URL Schemes")
+                .on_toggle(Message::ToggleRemoveQueriesSchemes),
+            checkbox(self.options.features.remove_required_capabilities)
+                .label("Remove Required Device Capabilities")
+                .on_toggle(Message::ToggleRemoveRequiredCapabilities),
             text("Advanced:").size(12),
             checkbox(self.options.embedding.single_profile)
                 .label("Only Register Main Bundle")
@@ -279,9 +514,19 @@ impl PackageScreen {
             checkbox(self.options.features.support_ellekit)
                 .label("Replace Substrate with ElleKit")
                 .on_toggle(Message::ToggleElleKit),
+            checkbox(self.options.features.thin_for_device)
+                .label("Thin Binaries for Device")
+                .on_toggle(Message::ToggleThinForDevice),
+            checkbox(self.options.features.strip_symbols)
+                .label("Remove Debug Symbols")
+                .on_toggle(Message::ToggleStripSymbols),
+            self.view_hook_runtime(),
             checkbox(self.options.refresh)
                 .label("Auto Refresh [BETA]")
                 .on_toggle(Message::ToggleRefresh),
+            checkbox(self.options.reuse_stale_app_ids)
+                .label("Reuse Stale App IDs")
+                .on_toggle(Message::ToggleReuseAppIds),
             text("Mode:").size(12),
             pick_list(
                 &[SignerInstallMode::Install, SignerInstallMode::Export][..],
@@ -290,6 +535,18 @@ impl PackageScreen {
             )
             .style(appearance::s_pick_list)
             .placeholder("Select mode"),
+            text("Compression:").size(12),
+            pick_list(
+                &[
+                    SignerCompression::Deflate(None),
+                    SignerCompression::Deflate(Some(9)),
+                    SignerCompression::Store,
+                ][..],
+                Some(self.options.compression),
+                Message::UpdateCompression
+            )
+            .style(appearance::s_pick_list)
+            .placeholder("Select compression"),
             text("Signing:").size(12),
             pick_list(
                 &[SignerMode::Pem, SignerMode::Adhoc, SignerMode::None][..],
@@ -309,6 +566,11 @@ impl PackageScreen {
             SignerInstallMode::Install => (has_device, "Install"),
             SignerInstallMode::Export => (true, "Export"),
         };
+        // Encrypted binaries only break once they're resigned, a failed check could hide them
+        let needs_override = !self.encrypted_binaries.is_empty() || self.encryption_error.is_some();
+        let blocked = (self.checking_encryption || (needs_override && !self.allow_encrypted))
+            && self.options.mode != SignerMode::None;
+        let button_enabled = button_enabled && !blocked;
 
         container(
             row![
@@ -335,6 +597,164 @@ impl PackageScreen {
         .into()
     }
 
+    fn view_removable_bundles(&self) -> Element<'_, Message> {
+        if self.removable_bundles.is_empty() {
+            return text("No extensions found").size(12).into();
+        }
+
+        let mut bundle_list = column![
+            checkbox(self.options.removed_bundles == SignerBundleRemoval::RemoveAll)
+                .label("Remove All")
+                .text_size(12)
+                .on_toggle(Message::ToggleRemoveAllBundles)
+        ]
+        .spacing(4);
+
+        for removable in &self.removable_bundles {
+            let label = format!(
+                "{} ({})",
+                removable.name.as_deref().unwrap_or(&removable.path),
+                removable.identifier.as_deref().unwrap_or("???")
+            );
+            let path = removable.path.clone();
+
+            bundle_list = bundle_list.push(
+                checkbox(self.options.removed_bundles.should_remove(&removable.path))
+                    .label(label)
+                    .text_size(12)
+                    .on_toggle(move |value| Message::ToggleRemoveBundle(path.clone(), value)),
+            );
+        }
+
+        bundle_list.into()
+    }
+
+    fn view_encryption_warning(&self) -> Element<'_, Message> {
+        if self.checking_encryption {
+            return text("Checking for encrypted binaries...").size(12).into();
+        }
+
+        let heading = if let Some(error) = &self.encryption_error {
+            error.clone()
+        } else if !self.encrypted_binaries.is_empty() {
+            "FairPlay encrypted binaries found, the app will crash on launch once signed:"
+                .to_string()
+        } else {
+            return column![].into();
+        };
+
+        let mut warning = column![text(heading).size(12)].spacing(4);
+
+        for binary in &self.encrypted_binaries {
+            warning = warning.push(text(format!("- {}", binary)).size(12));
+        }
+
+        warning
+            .push(
+                checkbox(self.allow_encrypted)
+                    .label("Sign Anyway")
+                    .on_toggle(Message::ToggleAllowEncrypted),
+            )
+            .into()
+    }
+
+    fn view_entitlement_report(&self) -> Element<'_, Message> {
+        let check_enabled =
+            matches!(self.options.mode, SignerMode::Pem) && !self.checking_entitlements;
+
+        let mut report_list = column![
+            button(appearance::icon_text(
+                appearance::WRENCH,
+                if self.checking_entitlements {
+                    "Checking..."
+                } else {
+                    "Check Entitlements"
+                },
+                None
+            ))
+            .on_press_maybe(check_enabled.then_some(Message::CheckEntitlements))
+            .style(appearance::s_button)
+        ]
+        .spacing(4);
+
+        match &self.entitlement_report {
+            None => {}
+            Some(Err(e)) => {
+                report_list = report_list.push(text(format!("Failed to check: {}", e)).size(12));
+            }
+            Some(Ok(report)) if !report.has_issues() => {
+                report_list = report_list.push(
+                    text(format!(
+                        "All entitlements will be granted ({} team)",
+                        report.membership
+                    ))
+                    .size(12),
+                );
+            }
+            Some(Ok(report)) => {
+                for bundle in &report.bundles {
+                    for entry in &bundle.entitlements {
+                        if entry.status != EntitlementStatus::Supported {
+                            report_list = report_list.push(
+                                text(format!("[{}] {}: {}", entry.status, bundle.name, entry.key))
+                                    .size(12),
+                            );
+                        }
+                        for value in &entry.dropped_values {
+                            report_list = report_list.push(
+                                text(format!("[dropped] {}: {}", bundle.name, value)).size(12),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        report_list.into()
+    }
+
+    fn view_hook_runtime(&self) -> Element<'_, Message> {
+        let mut runtime_row = row![
+            text(format!("Runtime: {}", self.options.hook_runtime))
+                .size(12)
+                .width(Fill),
+            button(text("Choose").size(12))
+                .on_press(Message::SelectHookRuntime)
+                .style(appearance::s_button),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        if self.options.hook_runtime != SignerHookRuntime::default() {
+            runtime_row = runtime_row.push(
+                button(appearance::icon(appearance::MINUS))
+                    .on_press(Message::ResetHookRuntime)
+                    .style(appearance::s_button)
+                    .padding(6),
+            );
+        }
+
+        runtime_row.into()
+    }
+
+    fn view_tweak_repository(&self) -> Element<'_, Message> {
+        match &self.options.tweak_repository {
+            Some(path) => row![
+                text(format!("Dependencies from {}", path.display()))
+                    .size(12)
+                    .width(Fill),
+                button(appearance::icon(appearance::MINUS))
+                    .on_press(Message::ClearTweakRepository)
+                    .style(appearance::s_button)
+                    .padding(6)
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center)
+            .into(),
+            None => column![].into(),
+        }
+    }
+
     fn view_tweaks(&self) -> Element<'_, Message> {
         let tweaks = self.options.tweaks.as_ref();
 
@@ -345,11 +765,35 @@ impl PackageScreen {
 
             let mut tweak_list = column![].spacing(4);
 
+            // Main app, everything, or a single extension
+            let mut targets = vec![SignerTweakTarget::Main, SignerTweakTarget::All];
+            targets.extend(
+                self.removable_bundles
+                    .iter()
+                    .filter(|b| b.kind == RemovableBundleKind::PlugIn)
+                    .map(|b| SignerTweakTarget::Bundles(vec![b.path.clone()])),
+            );
+
             for (i, tweak) in tweaks.iter().enumerate() {
                 let tweak_row = row![
-                    text(tweak.file_name().and_then(|n| n.to_str()).unwrap_or("???"))
-                        .size(12)
-                        .width(Fill),
+                    text(
+                        tweak
+                            .path
+                            .file_name()
+                            .and_then(|n| n.to_str())
+                            .unwrap_or("???")
+                    )
+                    .size(12)
+                    .width(Fill),
+                    pick_list(targets.clone(), Some(tweak.target.clone()), move |t| {
+                        Message::UpdateTweakTarget(i, t)
+                    })
+                    .style(appearance::s_pick_list)
+                    .text_size(12),
+                    checkbox(!tweak.weak)
+                        .label("Required")
+                        .text_size(12)
+                        .on_toggle(move |value| Message::ToggleTweakRequired(i, value)),
                     button(appearance::icon(appearance::MINUS))
                         .on_press(Message::RemoveTweak(i))
                         .style(appearance::s_button)
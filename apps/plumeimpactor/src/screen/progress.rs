@@ -3,9 +3,10 @@ use std::sync::{Arc, Mutex, mpsc};
 use iced::Element;
 use iced::Length::Fill;
 use iced::Task;
-use iced::widget::{button, column, container, row, text};
+use iced::widget::{button, column, container, row, scrollable, text};
 
 use crate::appearance;
+use crate::subscriptions::WARNING_PREFIX;
 
 type ProgressReceiver = Arc<Mutex<mpsc::Receiver<(String, i32)>>>;
 
@@ -24,6 +25,7 @@ pub struct ProgressScreen {
     pub progress: i32,
     pub is_installing: bool,
     pub progress_rx: Option<ProgressReceiver>,
+    pub warnings: Vec<String>,
 }
 
 impl ProgressScreen {
@@ -33,6 +35,7 @@ impl ProgressScreen {
             progress: 0,
             is_installing: false,
             progress_rx: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -41,11 +44,15 @@ impl ProgressScreen {
         self.progress = 0;
         self.status = "Idle.".to_string();
         self.progress_rx = Some(rx);
+        self.warnings.clear();
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::InstallationProgress(status, progress) => {
+                if let Some(warning) = status.strip_prefix(WARNING_PREFIX) {
+                    self.warnings.push(warning.to_string());
+                }
                 self.status = status.clone();
                 self.progress = progress;
 
@@ -101,11 +108,18 @@ impl ProgressScreen {
     pub fn view(&self) -> Element<'_, Message> {
         let progress_bar = iced::widget::progress_bar(0.0..=100.0, self.progress as f32);
 
+        let warnings = self
+            .warnings
+            .iter()
+            .fold(column![].spacing(4), |col, warning| {
+                col.push(text(format!("⚠ {}", warning)).size(12))
+            });
+
         let screen_content = column![
             text("Installing application, this will take a moment. Do not disconnect the device until finished.").size(14),
             text(format!("{}% – {}", self.progress, self.status)).size(14),
             progress_bar,
-            container(text("")).height(Fill),
+            container(scrollable(warnings)).height(Fill),
         ]
         .spacing(appearance::THEME_PADDING);
 
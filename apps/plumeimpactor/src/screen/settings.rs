@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, text};
 use iced::{Alignment, Element, Fill, Task};
+use plume_core::developer::qh::teams::TeamMembership;
 use plume_store::AccountStore;
 
 use crate::appearance;
@@ -10,11 +11,12 @@ use crate::appearance;
 pub struct Team {
     pub name: String,
     pub id: String,
+    pub membership: TeamMembership,
 }
 
 impl std::fmt::Display for Team {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} ({})", self.name, self.id)
+        write!(f, "{} ({}) - {}", self.name, self.id, self.membership)
     }
 }
 
@@ -193,6 +193,34 @@ pub(crate) fn installation_progress_listener(
     }
 }
 
+/// Installation statuses starting with this stay listed on the progress screen.
+pub(crate) const WARNING_PREFIX: &str = "Warning: ";
+
+// Tweak changes that didn't go as asked
+fn send_tweak_issues(signer: &plume_utils::Signer, send: &impl Fn(String, i32)) {
+    for skipped in &signer.skipped_dylibs {
+        send(format!("{}{}", WARNING_PREFIX, skipped), 70);
+    }
+    for dependency in &signer.unresolved_dependencies {
+        send(
+            format!(
+                "{}{}, the tweak may crash on launch",
+                WARNING_PREFIX, dependency
+            ),
+            70,
+        );
+    }
+    for package in &signer.missing_packages {
+        send(
+            format!("{}Unsatisfied dependency {}", WARNING_PREFIX, package),
+            70,
+        );
+    }
+    for preference in &signer.unsupported_preferences {
+        send(format!("{}{}", WARNING_PREFIX, preference), 70);
+    }
+}
+
 pub(crate) async fn run_installation(
     package: &plume_utils::Package,
     device: Option<&Device>,
@@ -212,6 +240,15 @@ pub(crate) async fn run_installation(
 
     send("Preparing package...".to_string(), 10);
 
+    if options.features.thin_for_device {
+        if let Some(dev) = device.filter(|d| !d.is_mac) {
+            match dev.cpu_architecture().await {
+                Ok(architecture) => options.thin_architecture = Some(architecture),
+                Err(e) => log::warn!("Not thinning, couldn't read the device architecture: {}", e),
+            }
+        }
+    }
+
     match options.mode {
         SignerMode::Pem => {
             let Some(account) = account else {
@@ -281,10 +318,41 @@ pub(crate) async fn run_installation(
                 .modify_bundle(&bundle, &Some(team_id.clone()))
                 .await
                 .map_err(|e| e.to_string())?;
-            signer
+            send_tweak_issues(&signer, &send);
+
+            if let Some(report) = &signer.thinning {
+                send(report.to_string(), 70);
+            }
+
+            if let Some(store) = store.as_deref_mut() {
+                let quota = signer
+                    .ensure_app_id_quota(&bundle, &session, team_id, store)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if let Some(quota) = quota {
+                    send(format!("App ID quota: {}", quota), 70);
+                }
+            }
+
+            // App IDs created before a failure still count towards the quota
+            let registered = signer
                 .register_bundle(&bundle, &session, team_id, false)
+                .await;
+
+            if let Some(store) = store.as_deref_mut() {
+                store
+                    .add_app_ids(signer.registered_app_ids.clone())
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+
+            registered.map_err(|e| e.to_string())?;
+
+            signer
+                .ensure_profiles_compatible(&bundle, &session, team_id, device)
                 .await
                 .map_err(|e| e.to_string())?;
+
             signer
                 .sign_bundle(&bundle)
                 .await
@@ -306,6 +374,10 @@ pub(crate) async fn run_installation(
                 .modify_bundle(&bundle, &None)
                 .await
                 .map_err(|e| e.to_string())?;
+            send_tweak_issues(&signer, &send);
+            if let Some(report) = &signer.thinning {
+                send(report.to_string(), 70);
+            }
             signer
                 .sign_bundle(&bundle)
                 .await
@@ -369,7 +441,7 @@ pub(crate) async fn run_installation(
             send("Exporting...".to_string(), 90);
 
             let archive_path = package
-                .get_archive_based_on_path(&package_file.bundle_dir())
+                .get_archive_based_on_path(&package_file.bundle_dir(), options.compression)
                 .map_err(|e| e.to_string())?;
 
             let file = rfd::AsyncFileDialog::new()
@@ -569,8 +641,52 @@ pub(crate) async fn fetch_teams(
         .teams
         .into_iter()
         .map(|t| crate::screen::settings::Team {
+            membership: t.membership(),
             name: t.name,
             id: t.team_id,
         })
         .collect())
 }
+
+pub(crate) async fn fetch_entitlement_report(
+    account: &plume_store::GsaAccount,
+    package: &plume_utils::Package,
+    options: &plume_utils::SignerOptions,
+) -> Result<plume_utils::EntitlementReport, String> {
+    use plume_core::{AnisetteConfiguration, developer::DeveloperSession};
+    use plume_utils::Signer;
+
+    let session = DeveloperSession::new(
+        account.adsid().clone(),
+        account.xcode_gs_token().clone(),
+        AnisetteConfiguration::default().set_configuration_path(crate::defaults::get_data_path()),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let team_id = if account.team_id().is_empty() {
+        session
+            .qh_list_teams()
+            .await
+            .map_err(|e| e.to_string())?
+            .teams
+            .first()
+            .map(|t| t.team_id.clone())
+            .ok_or_else(|| "No teams available for this account".to_string())?
+    } else {
+        account.team_id().clone()
+    };
+
+    // Installs extract into the package's own stage, keep out of their way
+    let package = package.restage().map_err(|e| e.to_string())?;
+    let report = match package.get_package_bundle() {
+        Ok(bundle) => Signer::new(None, options.clone())
+            .entitlement_report(&bundle, &session, &team_id)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    package.remove_package_stage();
+
+    report
+}
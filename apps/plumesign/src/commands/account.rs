@@ -109,7 +109,7 @@ pub async fn execute(args: AccountArgs) -> Result<()> {
     }
 }
 
-fn get_settings_path() -> PathBuf {
+pub fn get_settings_path() -> PathBuf {
     get_data_path().join("accounts.json")
 }
 
@@ -267,7 +267,7 @@ pub async fn teams(session: &DeveloperSession) -> Result<String> {
 
     let team_names: Vec<String> = teams
         .iter()
-        .map(|t| format!("{} ({})", t.name, t.team_id))
+        .map(|t| format!("{} ({}) - {}", t.name, t.team_id, t.membership()))
         .collect();
 
     let selection = Select::new().items(&team_names).default(0).interact()?;
@@ -16,19 +16,100 @@ pub struct MachArgs {
     /// Add a dylib dependency (e.g., @rpath/MyLib.dylib)
     #[arg(long, value_name = "DYLIB_PATH")]
     pub add_dylib: Option<String>,
+    /// Add the dylib with LC_LOAD_WEAK_DYLIB, so launch doesn't fail without it
+    #[arg(long, requires = "add_dylib")]
+    pub weak: bool,
+    /// List LC_RPATH entries
+    #[arg(long)]
+    pub list_rpaths: bool,
+    /// Add an LC_RPATH entry (e.g., @executable_path/Frameworks)
+    #[arg(long, value_name = "RPATH")]
+    pub add_rpath: Option<String>,
+    /// Remove an LC_RPATH entry
+    #[arg(long, value_name = "RPATH")]
+    pub remove_rpath: Option<String>,
     /// Replace an existing dylib dependency
     #[arg(long, value_names = &["OLD", "NEW"], num_args = 2)]
     pub replace_dylib: Option<Vec<String>>,
     /// Set the SDK version (e.g., 26.0.0)
     #[arg(long, value_name = "SDK_VERSION")]
     pub sdk_version: Option<String>,
+    /// List the architecture of every slice
+    #[arg(long)]
+    pub archs: bool,
+    /// Show how many bytes each slice has left for new load commands
+    #[arg(long)]
+    pub header_space: bool,
+    /// Slice to inspect with --list-dylibs and --entitlements (e.g., arm64e)
+    #[arg(long, value_name = "ARCH")]
+    pub arch: Option<String>,
+    /// Write a single slice as a thin binary to --output
+    #[arg(long, value_name = "ARCH", requires = "output")]
+    pub thin: Option<String>,
+    /// Remove slices (e.g., armv7 x86_64)
+    #[arg(long, value_name = "ARCH", num_args = 1..)]
+    pub remove_arch: Option<Vec<String>>,
+    /// Combine BINARY with other binaries into a universal binary at --output
+    #[arg(long, value_name = "OTHER", num_args = 1.., requires = "output")]
+    pub merge: Option<Vec<PathBuf>>,
+    /// Output path for --thin and --merge
+    #[arg(long, short, value_name = "OUTPUT")]
+    pub output: Option<PathBuf>,
 }
 
 pub async fn execute(args: MachArgs) -> Result<()> {
+    if let (Some(others), Some(output)) = (&args.merge, &args.output) {
+        let mut binaries = vec![args.binary.clone()];
+        binaries.extend(others.iter().cloned());
+        MachO::merge(&binaries, output)?;
+        return Ok(());
+    }
+
     let mut macho = MachO::new(&args.binary)?;
 
+    if args.archs {
+        for arch in macho.architectures() {
+            println!("{arch}");
+        }
+        return Ok(());
+    }
+
+    if args.header_space {
+        for (arch, padding) in macho.header_padding()? {
+            println!("{arch}: {padding} bytes");
+        }
+        return Ok(());
+    }
+
+    if let (Some(arch), Some(output)) = (&args.thin, &args.output) {
+        macho.extract_slice(arch, output)?;
+        return Ok(());
+    }
+
+    if let Some(archs) = &args.remove_arch {
+        macho.remove_slices(&archs.iter().map(String::as_str).collect::<Vec<_>>())?;
+        return Ok(());
+    }
+
     if let Some(dylib_path) = &args.add_dylib {
-        macho.add_dylib(dylib_path)?;
+        macho.add_dylib(dylib_path, args.weak)?;
+        return Ok(());
+    }
+
+    if args.list_rpaths {
+        for rpath in macho.rpaths()? {
+            println!("{rpath}");
+        }
+        return Ok(());
+    }
+
+    if let Some(rpath) = &args.add_rpath {
+        macho.add_rpath(rpath)?;
+        return Ok(());
+    }
+
+    if let Some(rpath) = &args.remove_rpath {
+        macho.remove_rpath(rpath)?;
         return Ok(());
     }
 
@@ -40,14 +121,11 @@ pub async fn execute(args: MachArgs) -> Result<()> {
     }
 
     if args.list_dylibs {
-        // TODO: add index argument
-        let d = macho
-            .macho_file()
-            .nth_macho(0)
-            .unwrap()
-            .dylib_load_paths()
-            .unwrap();
-        for path in d {
+        let slice = match &args.arch {
+            Some(arch) => macho.slice(arch)?,
+            None => macho.macho_file().nth_macho(0)?,
+        };
+        for path in slice.dylib_load_paths()? {
             println!("{path}");
         }
         return Ok(());
@@ -58,7 +136,10 @@ pub async fn execute(args: MachArgs) -> Result<()> {
         return Ok(());
     }
 
-    let entitlements = macho.entitlements();
+    let entitlements = match &args.arch {
+        Some(arch) => &macho.slice(arch)?.embedded_entitlements()?,
+        None => macho.entitlements(),
+    };
     if args.entitlements {
         if let Some(ent) = entitlements {
             let mut buf = Vec::new();
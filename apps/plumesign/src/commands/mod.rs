@@ -3,7 +3,10 @@ use clap::{Parser, Subcommand};
 pub mod account;
 pub mod device;
 pub mod macho;
+pub mod profile;
 pub mod sign;
+pub mod tweak;
+pub mod verify;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -29,4 +32,10 @@ pub enum Commands {
     Account(account::AccountArgs),
     /// Device management commands
     Device(device::DeviceArgs),
+    /// Inspect provisioning profiles
+    Profile(profile::ProfileArgs),
+    /// Verify the code signature of a signed app bundle or package
+    Verify(verify::VerifyArgs),
+    /// Inspect and remove tweaks injected into an app
+    Tweak(tweak::TweakArgs),
 }
@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use plume_core::MobileProvision;
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub command: ProfileCommands,
+}
+
+#[derive(Debug, Subcommand)]
+#[command(arg_required_else_help = true)]
+pub enum ProfileCommands {
+    /// Print everything inside a provisioning profile
+    Inspect(InspectArgs),
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct InspectArgs {
+    /// Path to the .mobileprovision file
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+    /// Check whether this device UDID is included in the profile
+    #[arg(long, value_name = "UDID")]
+    pub udid: Option<String>,
+}
+
+pub async fn execute(args: ProfileArgs) -> Result<()> {
+    match args.command {
+        ProfileCommands::Inspect(inspect_args) => inspect(inspect_args).await,
+    }
+}
+
+async fn inspect(args: InspectArgs) -> Result<()> {
+    let profile = MobileProvision::load_with_path(&args.file)?;
+
+    println!("Name: {}", profile.name());
+    println!("UUID: {}", profile.uuid());
+    println!(
+        "App ID Name: {}",
+        profile.app_id_name().map(String::as_str).unwrap_or("-")
+    );
+    println!("Bundle ID: {}", profile.bundle_id().unwrap_or_default());
+    println!(
+        "Team: {} ({})",
+        profile.team_name().map(String::as_str).unwrap_or("-"),
+        profile.team_identifiers().join(", ")
+    );
+    println!("Platforms: {}", profile.platforms().join(", "));
+    println!(
+        "Created: {}",
+        profile
+            .creation_date()
+            .map(|d| d.to_xml_format())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    println!("Expires: {}", profile.expiration_date().to_xml_format());
+
+    if profile.provisions_all_devices() {
+        println!("Devices: all");
+    } else {
+        println!("Devices ({}):", profile.provisioned_devices().len());
+        for udid in profile.provisioned_devices() {
+            println!("  {udid}");
+        }
+    }
+
+    println!("Certificates ({}):", profile.developer_certificates().len());
+    for cert in profile.developer_certificates() {
+        println!(
+            "  {}",
+            cert.subject_common_name()
+                .unwrap_or_else(|| "???".to_string())
+        );
+        println!(
+            "    Serial: {}",
+            to_hex(cert.serial_number_asn1().as_slice())
+        );
+        println!(
+            "    Valid: {} - {}",
+            cert.validity_not_before(),
+            cert.validity_not_after()
+        );
+        println!("    SHA-1: {}", to_hex(cert.sha1_fingerprint()?.as_ref()));
+    }
+
+    println!("Entitlements:");
+    for (key, value) in profile.entitlements() {
+        println!("  {key}: {value:?}");
+    }
+
+    if let Some(udid) = &args.udid {
+        if profile.provisions_device(udid) {
+            println!("Device {udid} is included in this profile");
+        } else {
+            return Err(anyhow::anyhow!(
+                "Device {udid} is not included in this profile"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
@@ -2,13 +2,18 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::Args;
+use dialoguer::Confirm;
 
 use plume_core::{CertificateIdentity, MobileProvision};
-use plume_utils::{Bundle, Package, Signer, SignerMode, SignerOptions};
+use plume_store::AccountStore;
+use plume_utils::{
+    Bundle, Package, Signer, SignerCompression, SignerFeatures, SignerHookRuntime, SignerMode,
+    SignerOptions, SignerPathRewrite, SignerTweak,
+};
 
 use crate::{
     commands::{
-        account::{get_authenticated_account, teams},
+        account::{get_authenticated_account, get_settings_path, teams},
         device::select_device,
     },
     get_data_path,
@@ -38,9 +43,54 @@ pub struct SignArgs {
     /// Custom bundle version to set
     #[arg(long = "custom-version", value_name = "VERSION")]
     pub version: Option<String>,
-    /// Perform ad-hoc signing (no certificate required)
-    #[arg(long, short, num_args = 1..)]
-    pub tweaks: Option<Vec<PathBuf>>,
+    /// Remove CFBundleURLTypes from every bundle
+    #[arg(long)]
+    pub remove_url_schemes: bool,
+    /// Remove UISupportedDevices from every bundle
+    #[arg(long)]
+    pub remove_supported_devices: bool,
+    /// Remove LSApplicationQueriesSchemes from every bundle
+    #[arg(long)]
+    pub remove_queries_schemes: bool,
+    /// Remove UIRequiredDeviceCapabilities from every bundle
+    #[arg(long)]
+    pub remove_required_capabilities: bool,
+    /// Tweaks to inject, optionally followed by `=main`, `=all` or `=PlugIns/Share.appex,...`
+    #[arg(long, short, num_args = 1.., value_parser = parse_tweak)]
+    pub tweaks: Option<Vec<SignerTweak>>,
+    /// Inject tweaks with LC_LOAD_DYLIB, so the app won't launch if one fails to load
+    #[arg(long, requires = "tweaks")]
+    pub require_tweaks: bool,
+    /// Extra rewrites for jailbreak paths tweaks link against, as `/path=@rpath/path`
+    #[arg(long = "rewrite", value_name = "FROM=TO")]
+    pub rewrites: Vec<SignerPathRewrite>,
+    /// Folder of .debs or APT `Packages` index to install tweak dependencies from
+    #[arg(long, value_name = "PATH", requires = "tweaks")]
+    pub tweak_repo: Option<PathBuf>,
+    /// Hooking runtime to bundle instead of the built-in ElleKit (.deb, .framework or .dylib)
+    #[arg(long, value_name = "PATH")]
+    pub hook_runtime: Option<PathBuf>,
+    /// Strip slices the device's CPU can't run from every binary
+    #[arg(long, requires = "register_and_install", conflicts_with = "thin_arch")]
+    pub thin: bool,
+    /// Strip slices this architecture can't run from every binary (e.g. arm64)
+    #[arg(long, value_name = "ARCH")]
+    pub thin_arch: Option<String>,
+    /// Remove .bcsymbolmap files and the IPA's SwiftSupport, Symbols and BCSymbolMaps folders
+    #[arg(long)]
+    pub strip_symbols: bool,
+    /// Sign even if binaries are still FairPlay encrypted (the app will crash on launch)
+    #[arg(long)]
+    pub allow_encrypted: bool,
+    /// Delete stale App IDs created by plumesign if the free account quota runs out
+    #[arg(long)]
+    pub reuse_app_ids: bool,
+    /// Print which entitlements will be granted, blocked or dropped, then exit
+    #[arg(long, requires = "apple_id", conflicts_with = "register_and_install")]
+    pub entitlement_report: bool,
+    /// Print the entitlement report as JSON
+    #[arg(long, requires = "entitlement_report")]
+    pub json: bool,
     /// Register device and install after signing
     #[arg(long)]
     pub register_and_install: bool,
@@ -50,12 +100,74 @@ pub struct SignArgs {
     /// Output path for signed .ipa (only for .ipa input)
     #[arg(long, short, value_name = "OUTPUT")]
     pub output: Option<PathBuf>,
+    /// Deflate level (0-9) used when writing the output .ipa
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(i64).range(0..=9),
+        conflicts_with = "store"
+    )]
+    pub compression_level: Option<i64>,
+    /// Write the output .ipa without compression
+    #[arg(long)]
+    pub store: bool,
     /// Install to connected Mac (arm64 only)
     #[cfg(all(target_os = "macos", target_arch = "aarch64"))]
     #[arg(short = 'm', long = "mac", value_name = "MAC", conflicts_with = "udid")]
     pub mac: bool,
 }
 
+fn parse_tweak(value: &str) -> Result<SignerTweak, String> {
+    // Paths containing `=` are taken as-is when they exist
+    if PathBuf::from(value).exists() {
+        return Ok(SignerTweak::new(PathBuf::from(value)));
+    }
+
+    match value.rsplit_once('=') {
+        Some((path, target)) => Ok(SignerTweak {
+            path: PathBuf::from(path),
+            target: target.parse()?,
+            weak: true,
+        }),
+        None => Ok(SignerTweak::new(PathBuf::from(value))),
+    }
+}
+
+fn check_encrypted(encrypted: Vec<String>, allow_encrypted: bool) -> Result<()> {
+    if encrypted.is_empty() {
+        return Ok(());
+    }
+
+    if !allow_encrypted {
+        return Err(plume_utils::Error::BinariesEncrypted(encrypted).into());
+    }
+
+    for binary in &encrypted {
+        log::warn!(
+            "⚠️  {} is FairPlay encrypted, the app will crash on launch",
+            binary
+        );
+    }
+
+    Ok(())
+}
+
+// Tweak changes that didn't go as asked, signing carries on regardless
+fn report_tweak_issues(signer: &Signer) {
+    for skipped in &signer.skipped_dylibs {
+        log::warn!("⚠️  {}", skipped);
+    }
+    for dependency in &signer.unresolved_dependencies {
+        log::warn!("⚠️  {}, the tweak may crash on launch", dependency);
+    }
+    for package in &signer.missing_packages {
+        log::warn!("⚠️  Unsatisfied dependency {}", package);
+    }
+    for preference in &signer.unsupported_preferences {
+        log::warn!("⚠️  {}", preference);
+    }
+}
+
 pub async fn execute(args: SignArgs) -> Result<()> {
     if !args.package.is_dir() && !args.apple_id && args.output.is_none() {
         return Err(anyhow::anyhow!(
@@ -63,14 +175,48 @@ pub async fn execute(args: SignArgs) -> Result<()> {
         ));
     }
 
+    let compression = if args.store {
+        SignerCompression::Store
+    } else {
+        SignerCompression::Deflate(args.compression_level)
+    };
+
     let mut options = SignerOptions {
         custom_identifier: args.bundle_identifier,
         custom_name: args.name,
         custom_version: args.version,
-        tweaks: args.tweaks,
+        tweaks: args.tweaks.map(|tweaks| {
+            tweaks
+                .into_iter()
+                .map(|tweak| SignerTweak {
+                    weak: !args.require_tweaks,
+                    ..tweak
+                })
+                .collect()
+        }),
+        tweak_repository: args.tweak_repo,
+        hook_runtime: args
+            .hook_runtime
+            .map(SignerHookRuntime::Custom)
+            .unwrap_or_default(),
+        reuse_stale_app_ids: args.reuse_app_ids,
+        thin_architecture: args.thin_arch,
+        compression,
+        features: SignerFeatures {
+            remove_url_schemes: args.remove_url_schemes,
+            remove_supported_devices: args.remove_supported_devices,
+            remove_queries_schemes: args.remove_queries_schemes,
+            remove_required_capabilities: args.remove_required_capabilities,
+            thin_for_device: args.thin,
+            strip_symbols: args.strip_symbols,
+            ..Default::default()
+        },
         ..Default::default()
     };
 
+    // Checked before the defaults, so they can override them
+    options.path_rewrites.splice(0..0, args.rewrites);
+
     let (bundle, package) = if args.package.is_dir() {
         log::warn!("⚠️  Signing bundle in place: {}", args.package.display());
         if args.output.is_some() {
@@ -78,9 +224,15 @@ pub async fn execute(args: SignArgs) -> Result<()> {
                 "Note: -o/--output flag is ignored for .app bundles (in-place signing only)"
             );
         }
-        (Bundle::new(&args.package)?, None)
+        let bundle = Bundle::new(&args.package)?;
+        check_encrypted(bundle.encrypted_binaries()?, args.allow_encrypted)?;
+        (bundle, None)
     } else {
         let pkg = Package::new(args.package.clone())?;
+        if let Err(e) = check_encrypted(pkg.get_encrypted_binaries()?, args.allow_encrypted) {
+            pkg.remove_package_stage();
+            return Err(e);
+        }
         let bundle = pkg.get_package_bundle()?;
         (bundle, Some(pkg))
     };
@@ -137,10 +289,34 @@ pub async fn execute(args: SignArgs) -> Result<()> {
         None
     };
 
+    if signer.options.features.thin_for_device {
+        if let Some(dev) = device.as_ref().filter(|d| !d.is_mac) {
+            let architecture = dev.cpu_architecture().await?;
+            log::info!("Thinning binaries to {}", architecture);
+            signer.options.thin_architecture = Some(architecture);
+        }
+    }
+
     if let Some((session, team_id)) = team_id_opt {
+        if args.entitlement_report {
+            let report = signer.entitlement_report(&bundle, &session, &team_id).await;
+            if let Some(pkg) = package {
+                pkg.remove_package_stage();
+            }
+
+            let report = report?;
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", report);
+            }
+            return Ok(());
+        }
+
         signer
             .modify_bundle(&bundle, &Some(team_id.clone()))
             .await?;
+        report_tweak_issues(&signer);
 
         if let Some(ref dev) = device {
             log::info!("Registering device: {} ({})", dev.name, dev.udid);
@@ -149,8 +325,46 @@ pub async fn execute(args: SignArgs) -> Result<()> {
                 .await?;
         }
 
-        signer
+        let mut store = AccountStore::load(&Some(get_settings_path())).await?;
+        let membership = session.qh_team_membership(&team_id).await?;
+        log::info!("Team membership: {}", membership);
+
+        if membership.is_free() {
+            let report = signer
+                .app_id_quota(&bundle, &session, &team_id, &store)
+                .await?;
+            log::info!(
+                "App ID quota: {} ({} new App IDs needed)",
+                report.quota,
+                report.needed.len()
+            );
+
+            if report.shortfall() > 0
+                && !report.stale.is_empty()
+                && !signer.options.reuse_stale_app_ids
+            {
+                signer.options.reuse_stale_app_ids = Confirm::new()
+                    .with_prompt(format!(
+                        "App ID quota exhausted. Delete {} stale App ID(s) created by plumesign?",
+                        report.shortfall().min(report.stale.len())
+                    ))
+                    .default(false)
+                    .interact()?;
+            }
+
+            signer
+                .ensure_app_id_quota(&bundle, &session, &team_id, &mut store)
+                .await?;
+        }
+
+        // App IDs created before a failure still count towards the quota
+        let registered = signer
             .register_bundle(&bundle, &session, &team_id, false)
+            .await;
+        store.add_app_ids(signer.registered_app_ids.clone()).await?;
+        registered?;
+        signer
+            .ensure_profiles_compatible(&bundle, &session, &team_id, device.as_ref())
             .await?;
         signer.sign_bundle(&bundle).await?;
 
@@ -178,6 +392,7 @@ pub async fn execute(args: SignArgs) -> Result<()> {
         }
     } else {
         signer.modify_bundle(&bundle, &None).await?;
+        report_tweak_issues(&signer);
         signer.sign_bundle(&bundle).await?;
 
         if let Some(dev) = device {
@@ -193,7 +408,7 @@ pub async fn execute(args: SignArgs) -> Result<()> {
 
     if let Some(pkg) = package {
         if let Some(output_path) = args.output {
-            let archived_path = pkg.get_archive_based_on_path(&args.package.clone())?;
+            let archived_path = pkg.get_archive_based_on_path(bundle.bundle_dir(), compression)?;
             tokio::fs::copy(&archived_path, &output_path).await?;
             log::info!("Saved signed package to: {}", output_path.display());
             pkg.remove_package_stage();
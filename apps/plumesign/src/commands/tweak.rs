@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use plume_utils::{
+    Bundle, ExtractLimits, Package, SignerCompression, SignerHookRuntime, SignerPathRewrite, Tweak,
+};
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct TweakArgs {
+    #[command(subcommand)]
+    pub command: TweakCommands,
+}
+
+#[derive(Debug, Subcommand)]
+#[command(arg_required_else_help = true)]
+pub enum TweakCommands {
+    /// List the libraries injected into an app and its extensions
+    List(ListArgs),
+    /// Remove injected libraries and their files from an app
+    Remove(RemoveArgs),
+    /// Show what injecting a tweak would do, without an app
+    Inspect(InspectArgs),
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct ListArgs {
+    /// Path to the app bundle or package (.app or .ipa)
+    #[arg(value_name = "PACKAGE")]
+    pub package: PathBuf,
+    /// Print the list as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct RemoveArgs {
+    /// Path to the app bundle or package (.app or .ipa)
+    #[arg(value_name = "PACKAGE")]
+    pub package: PathBuf,
+    /// Load paths or file names to remove (e.g. Tweak.dylib)
+    #[arg(value_name = "NAME", required = true, num_args = 1..)]
+    pub names: Vec<String>,
+    /// Output path for the modified .ipa (only for .ipa input)
+    #[arg(long, short, value_name = "OUTPUT")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct InspectArgs {
+    /// Path to the tweak (.deb, .dylib, .framework, .bundle or .appex)
+    #[arg(value_name = "TWEAK")]
+    pub tweak: PathBuf,
+    /// Extra rewrites for jailbreak paths the tweak links against, as `/path=@rpath/path`
+    #[arg(long = "rewrite", value_name = "FROM=TO")]
+    pub rewrites: Vec<SignerPathRewrite>,
+    /// Hooking runtime that would be bundled instead of the built-in ElleKit (.deb, .framework or .dylib)
+    #[arg(long, value_name = "PATH")]
+    pub hook_runtime: Option<PathBuf>,
+    /// Print the inspection as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub async fn execute(args: TweakArgs) -> Result<()> {
+    match args.command {
+        TweakCommands::List(list_args) => list(list_args).await,
+        TweakCommands::Remove(remove_args) => remove(remove_args).await,
+        TweakCommands::Inspect(inspect_args) => inspect(inspect_args).await,
+    }
+}
+
+fn open_package(path: &PathBuf) -> Result<(Bundle, Option<Package>)> {
+    if path.is_dir() {
+        Ok((Bundle::new(path)?, None))
+    } else {
+        let pkg = Package::new(path.clone())?;
+        let bundle = pkg.get_package_bundle()?;
+        Ok((bundle, Some(pkg)))
+    }
+}
+
+async fn list(args: ListArgs) -> Result<()> {
+    let (bundle, package) = open_package(&args.package)?;
+    let injected = Tweak::list_injected(&bundle)?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&injected)?);
+    } else if injected.is_empty() {
+        println!("No injected tweaks found");
+    } else {
+        for tweak in &injected {
+            let loaded_by = tweak
+                .loaded_by
+                .iter()
+                .map(|b| if b.is_empty() { "Main App" } else { b.as_str() })
+                .collect::<Vec<_>>();
+
+            let weak = if tweak.weak { " (weak)" } else { "" };
+            println!("{}{}", tweak.load_path, weak);
+            println!("    File: {}", tweak.file.as_deref().unwrap_or("(missing)"));
+            println!("    Loaded by: {}", loaded_by.join(", "));
+        }
+    }
+
+    if let Some(pkg) = package {
+        pkg.remove_package_stage();
+    }
+
+    Ok(())
+}
+
+async fn remove(args: RemoveArgs) -> Result<()> {
+    if !args.package.is_dir() && args.output.is_none() {
+        return Err(anyhow::anyhow!(
+            "-o/--output is required when removing tweaks from an .ipa."
+        ));
+    }
+
+    let (bundle, package) = open_package(&args.package)?;
+
+    for name in &args.names {
+        let removed = Tweak::remove_injected(&bundle, name)?;
+        log::info!("Removed {}", removed.load_path);
+    }
+
+    log::warn!("The app's signature is no longer valid, sign it again before installing.");
+
+    if let Some(pkg) = package {
+        if let Some(output_path) = args.output {
+            let archived_path =
+                pkg.get_archive_based_on_path(bundle.bundle_dir(), SignerCompression::default())?;
+            tokio::fs::copy(&archived_path, &output_path).await?;
+            log::info!("Saved modified package to: {}", output_path.display());
+        }
+        pkg.remove_package_stage();
+    }
+
+    Ok(())
+}
+
+async fn inspect(args: InspectArgs) -> Result<()> {
+    // Checked before the defaults, so they can override them
+    let mut rules = args.rewrites;
+    rules.extend(SignerPathRewrite::defaults());
+
+    let runtime = args
+        .hook_runtime
+        .map(SignerHookRuntime::Custom)
+        .unwrap_or_default();
+    let runtime = Tweak::runtime_load_path(&runtime, ExtractLimits::default()).await?;
+
+    let inspection = Tweak::inspect(
+        &args.tweak,
+        &rules,
+        Some(&runtime),
+        ExtractLimits::default(),
+    )
+    .await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+    } else {
+        print!("{}", inspection);
+    }
+
+    Ok(())
+}
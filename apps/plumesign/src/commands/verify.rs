@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::Args;
+use plume_utils::{Bundle, Package};
+
+#[derive(Debug, Args)]
+#[command(arg_required_else_help = true)]
+pub struct VerifyArgs {
+    /// Path to the signed app bundle or package (.app or .ipa)
+    #[arg(value_name = "PACKAGE")]
+    pub package: PathBuf,
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Exits with status 1 when any bundle fails verification.
+pub async fn execute(args: VerifyArgs) -> Result<()> {
+    let (bundle, package) = if args.package.is_dir() {
+        (Bundle::new(&args.package)?, None)
+    } else {
+        let pkg = Package::new(args.package.clone())?;
+        match pkg.get_package_bundle() {
+            Ok(bundle) => (bundle, Some(pkg)),
+            Err(e) => {
+                pkg.remove_package_stage();
+                return Err(e.into());
+            }
+        }
+    };
+
+    let results = bundle.verify();
+    if let Some(pkg) = package {
+        pkg.remove_package_stage();
+    }
+    let results = results?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        for result in &results {
+            if result.passed() {
+                println!("[pass] {}", result.path);
+            } else {
+                println!("[fail] {}", result.path);
+                for problem in &result.problems {
+                    println!("    {problem}");
+                }
+            }
+        }
+    }
+
+    if results.iter().any(|r| !r.passed()) {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
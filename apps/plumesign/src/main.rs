@@ -19,6 +19,9 @@ async fn main() -> anyhow::Result<()> {
         Commands::MachO(args) => commands::macho::execute(args).await?,
         Commands::Account(args) => commands::account::execute(args).await?,
         Commands::Device(args) => commands::device::execute(args).await?,
+        Commands::Profile(args) => commands::profile::execute(args).await?,
+        Commands::Verify(args) => commands::verify::execute(args).await?,
+        Commands::Tweak(args) => commands::tweak::execute(args).await?,
     }
 
     Ok(())
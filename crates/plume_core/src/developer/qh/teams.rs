@@ -1,5 +1,5 @@
 use plist::{Date, Integer, Value};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::Error;
 
@@ -15,6 +15,52 @@ impl DeveloperSession {
 
         Ok(response_data)
     }
+
+    /// Works out whether `team_id` is a free or paid (Apple Developer Program) team.
+    ///
+    /// The result is cached for the lifetime of the session.
+    pub async fn qh_team_membership(&self, team_id: &String) -> Result<TeamMembership, Error> {
+        if let Some(membership) = self.memberships.lock().await.get(team_id) {
+            return Ok(*membership);
+        }
+
+        let teams = self.qh_list_teams().await?.teams;
+
+        let membership = match teams.iter().find(|t| &t.team_id == team_id) {
+            Some(team) => team.membership(),
+            // Not every team shows up in listTeams (e.g. when only invited),
+            // so ask viewDeveloper about our own membership instead.
+            None => match self.qh_get_account_info(team_id).await {
+                Ok(response) => {
+                    let status = response.developer.developer_status;
+                    log::debug!("Team {} not listed, developer status: {}", team_id, status);
+                    if status.eq_ignore_ascii_case("active") {
+                        TeamMembership::Paid
+                    } else {
+                        TeamMembership::Free
+                    }
+                }
+                // Free restrictions only cost capabilities, not cached so the next call retries
+                Err(e) => {
+                    log::warn!(
+                        "Couldn't look up membership of team {}, treating it as free: {}",
+                        team_id,
+                        e
+                    );
+                    return Ok(TeamMembership::Free);
+                }
+            },
+        };
+
+        log::info!("Team {} membership: {}", team_id, membership);
+
+        self.memberships
+            .lock()
+            .await
+            .insert(team_id.clone(), membership);
+
+        Ok(membership)
+    }
 }
 
 #[allow(dead_code)]
@@ -43,6 +89,43 @@ pub struct Team {
     team_provisioning_settings: TeamProvisionSettings,
 }
 
+impl Team {
+    /// Free teams are flagged as Xcode-only and have no active program membership.
+    pub fn membership(&self) -> TeamMembership {
+        let has_active_membership = self
+            .memberships
+            .iter()
+            .any(|m| m.status.eq_ignore_ascii_case("active"));
+
+        if self.xcode_free_only || !has_active_membership {
+            TeamMembership::Free
+        } else {
+            TeamMembership::Paid
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TeamMembership {
+    Free,
+    Paid,
+}
+
+impl TeamMembership {
+    pub fn is_free(&self) -> bool {
+        matches!(self, TeamMembership::Free)
+    }
+}
+
+impl std::fmt::Display for TeamMembership {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeamMembership::Free => write!(f, "Free"),
+            TeamMembership::Paid => write!(f, "Paid"),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
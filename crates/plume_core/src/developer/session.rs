@@ -1,5 +1,6 @@
 use omnisette::AnisetteConfiguration;
 use reqwest::header::HeaderName;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -14,6 +15,7 @@ use crate::Error;
 use crate::auth::Account;
 use crate::auth::anisette_data::AnisetteData;
 use crate::developer::qh::QHResponseMeta;
+use crate::developer::qh::teams::TeamMembership;
 use crate::developer::v1::V1ErrorResponse;
 
 pub struct DeveloperSession {
@@ -21,6 +23,7 @@ pub struct DeveloperSession {
     client: Client,
     adsid: String,          // from grandslam's SPD "adsid"
     xcode_gs_token: String, // requested from spd initially // com.apple.gs.xcode.auth
+    pub(crate) memberships: Mutex<HashMap<String, TeamMembership>>, // team id -> detected membership
 }
 
 impl DeveloperSession {
@@ -43,6 +46,7 @@ impl DeveloperSession {
             client: account.client.clone(),
             adsid: adsid.into(),
             xcode_gs_token,
+            memberships: Mutex::new(HashMap::new()),
         })
     }
 
@@ -67,6 +71,7 @@ impl DeveloperSession {
             client,
             adsid,
             xcode_gs_token,
+            memberships: Mutex::new(HashMap::new()),
         };
 
         // we test the session by listing teams
@@ -8,6 +8,8 @@ use crate::developer_endpoint;
 use crate::Error;
 use std::collections::HashSet;
 
+use crate::developer::qh::teams::TeamMembership;
+
 const FREE_DEVELOPER_ACCOUNT_UNALLOWED_CAPABILITIES: &[&str] = &[
     "AUTOFILL_CREDENTIAL_PROVIDER",
     "APPLE_ID_AUTH",
@@ -20,6 +22,16 @@ const FREE_DEVELOPER_ACCOUNT_UNALLOWED_CAPABILITIES: &[&str] = &[
     "MDM_MANAGED_ASSOCIATED_DOMAINS",
 ];
 
+impl TeamMembership {
+    /// Capabilities Apple refuses to enable for this kind of team.
+    pub fn unallowed_capabilities(&self) -> &'static [&'static str] {
+        match self {
+            TeamMembership::Free => FREE_DEVELOPER_ACCOUNT_UNALLOWED_CAPABILITIES,
+            TeamMembership::Paid => &[],
+        }
+    }
+}
+
 impl DeveloperSession {
     pub async fn v1_list_capabilities(&self, team: &String) -> Result<CapabilitiesResponse, Error> {
         let endpoint = developer_endpoint!("/v1/capabilities");
@@ -43,13 +55,17 @@ impl DeveloperSession {
         id: &String,
         entitlements: &Dictionary,
     ) -> Result<(), Error> {
+        let unallowed = self
+            .qh_team_membership(team)
+            .await?
+            .unallowed_capabilities();
         let capabilities = self.v1_list_capabilities(team).await?.data;
         let entitlement_keys: HashSet<&str> = entitlements.keys().map(|k| k.as_str()).collect();
 
-        // Collect capability IDs that match entitlement keys and are allowed for free accounts
+        // Collect capability IDs that match entitlement keys and are allowed for this team
         let capabilities_to_enable: Vec<String> = capabilities
             .iter()
-            .filter(|cap| !FREE_DEVELOPER_ACCOUNT_UNALLOWED_CAPABILITIES.contains(&cap.id.as_str()))
+            .filter(|cap| !unallowed.contains(&cap.id.as_str()))
             .filter_map(|cap| {
                 cap.attributes
                     .entitlements
@@ -6,7 +6,9 @@ pub use apple_codesign::{AppleCodesignError, SettingsScope, SigningSettings, Uni
 
 pub use omnisette::AnisetteConfiguration;
 
-pub use utils::{CertificateIdentity, MachO, MachOExt, MobileProvision};
+pub use utils::{
+    CertificateIdentity, DylibLoadCommand, MachO, MachOExt, MobileProvision, TEAM_ID_REGEX,
+};
 
 use thiserror::Error as ThisError;
 #[derive(Debug, ThisError)]
@@ -15,6 +17,14 @@ pub enum Error {
     BundleExecutableMissing,
     #[error("Entitlements not found")]
     ProvisioningEntitlementsUnknown,
+    #[error("Invalid provisioning profile signature: {0}")]
+    ProvisioningSignature(String),
+    #[error("Provisioning profile '{0}' has expired")]
+    ProvisioningExpired(String),
+    #[error("Provisioning profile '{0}' does not include the signing certificate")]
+    ProvisioningCertificateMissing(String),
+    #[error("Provisioning profile '{profile}' does not include device {udid}")]
+    ProvisioningDeviceMissing { profile: String, udid: String },
     #[error("Missing certificate PEM data")]
     CertificatePemMissing,
     #[error("Certificate error: {0}")]
@@ -36,6 +46,20 @@ pub enum Error {
     Bad2faCode,
     #[error("Failed to parse")]
     Parse, // TODO: better parsing errors
+    #[error("No {0} slice in binary")]
+    MachOSliceMissing(String),
+    #[error("More than one {0} slice")]
+    MachOSliceDuplicate(String),
+    #[error("Removing every slice would leave an empty binary")]
+    MachONoSlicesLeft,
+    #[error(
+        "Not enough header space in the {arch} slice for new load commands: {needed} bytes needed, {available} available"
+    )]
+    MachOHeaderFull {
+        arch: String,
+        needed: usize,
+        available: usize,
+    },
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
     #[error("Plist error: {0}")]
@@ -1,25 +1,77 @@
 use std::fs;
+use std::io::{self, Read};
 use std::path::Path;
 
 use apple_codesign::{MachFile, MachOBinary, UniversalBinaryBuilder};
 use goblin::mach::{
     MachO as GoblinMachO,
-    cputype::CPU_TYPE_ARM64,
+    cputype::get_arch_name_from_types,
+    fat::{FAT_MAGIC, FAT_MAGIC_64},
+    header::{MH_MAGIC, MH_MAGIC_64},
     load_command::{
-        CommandVariant, LC_LAZY_LOAD_DYLIB, LC_LOAD_DYLIB, LC_LOAD_UPWARD_DYLIB,
-        LC_LOAD_WEAK_DYLIB, LC_REEXPORT_DYLIB,
+        CommandVariant, LC_CODE_SIGNATURE, LC_DYLIB_CODE_SIGN_DRS, LC_ENCRYPTION_INFO,
+        LC_ENCRYPTION_INFO_64, LC_LAZY_LOAD_DYLIB, LC_LOAD_DYLIB, LC_LOAD_UPWARD_DYLIB,
+        LC_LOAD_WEAK_DYLIB, LC_REEXPORT_DYLIB, LC_RPATH,
     },
 };
 use plist::{Dictionary, Value};
 
 use crate::Error;
 
+const DYLIB_COMMANDS: &[u32] = &[
+    LC_LOAD_DYLIB,
+    LC_LOAD_WEAK_DYLIB,
+    LC_REEXPORT_DYLIB,
+    LC_LAZY_LOAD_DYLIB,
+    LC_LOAD_UPWARD_DYLIB,
+];
+
+// Timestamp of every dylib command plume injects, "PLUM"
+const INJECTED_DYLIB_TIMESTAMP: u32 = 0x504c_554d;
+
+// sizeof(linkedit_data_command), signing adds one when the binary has none
+const CODE_SIGNATURE_COMMAND_SIZE: usize = 16;
+
+/// A dylib load command of a Mach-O slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DylibLoadCommand {
+    pub cmd: u32,
+    pub path: String,
+    pub timestamp: u32,
+    pub current_version: u32,
+    pub compatibility_version: u32,
+}
+
+impl DylibLoadCommand {
+    pub fn is_weak(&self) -> bool {
+        self.cmd == LC_LOAD_WEAK_DYLIB
+    }
+
+    /// Whether plume added this command when injecting a tweak.
+    pub fn is_injected(&self) -> bool {
+        self.path.starts_with("@rpath/") && self.timestamp == INJECTED_DYLIB_TIMESTAMP
+    }
+
+    /// Whether this looks like a command older releases injected, which were
+    /// weak with the fields ld64 writes. Apps weakly link their own frameworks
+    /// the same way, so this is only a guess.
+    pub fn is_legacy_injected(&self) -> bool {
+        self.path.starts_with("@rpath/")
+            && self.is_weak()
+            && self.timestamp == 2
+            && self.current_version == 0x10000
+            && self.compatibility_version == 0x10000
+    }
+}
+
 /// Represents a Mach-O file and its entitlements.
 pub struct MachO {
     #[allow(dead_code)]
     macho_file: MachFile<'static>,
     path: std::path::PathBuf,
     entitlements: Option<Dictionary>,
+    /// Whether the file has a fat header, thin files are written back thin.
+    universal: bool,
 }
 
 impl MachO {
@@ -29,11 +81,16 @@ impl MachO {
         let macho_data = Box::leak(macho_data.into_boxed_slice());
         let macho_file = MachFile::parse(macho_data)?; // macho_file.data is the full file data
         let entitlements = Self::extract_entitlements(&macho_file)?;
+        let universal = macho_data
+            .get(..4)
+            .map(|magic| u32::from_be_bytes([magic[0], magic[1], magic[2], magic[3]]))
+            .is_some_and(|magic| magic == FAT_MAGIC || magic == FAT_MAGIC_64);
 
         Ok(MachO {
             macho_file,
             path: path.as_ref().to_path_buf(),
             entitlements,
+            universal,
         })
     }
 
@@ -45,10 +102,173 @@ impl MachO {
         &self.entitlements
     }
 
+    /// Architecture of every slice, e.g. `arm64` and `arm64e`.
+    pub fn architectures(&self) -> Vec<String> {
+        self.macho_file
+            .iter_macho()
+            .map(|binary| arch_name(&binary.macho))
+            .collect()
+    }
+
+    /// Whether any slice is still FairPlay encrypted.
+    pub fn is_encrypted(&self) -> Result<bool, Error> {
+        for binary in self.macho_file.iter_macho() {
+            if binary.is_encrypted()? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// [`MachO::is_encrypted`] for a binary that isn't on disk, e.g. inside an archive.
+    /// Only the header and load commands of each slice are read, the rest is skipped.
+    pub fn is_encrypted_reader<R: Read>(mut reader: R) -> Result<bool, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let fat_magic = u32::from_be_bytes(magic);
+        if fat_magic != FAT_MAGIC && fat_magic != FAT_MAGIC_64 {
+            return Ok(read_slice_encryption(magic, &mut reader)?.0);
+        }
+
+        // fat_header and fat_arch entries are big endian, 64-bit ones widen the offset
+        let mut nfat_arch = [0u8; 4];
+        reader.read_exact(&mut nfat_arch)?;
+        let nfat_arch = u32::from_be_bytes(nfat_arch) as u64;
+        let arch_size = if fat_magic == FAT_MAGIC_64 { 32 } else { 20 };
+
+        let mut offsets = Vec::new();
+        let mut arch = vec![0u8; arch_size];
+        for _ in 0..nfat_arch {
+            reader.read_exact(&mut arch)?;
+            offsets.push(if fat_magic == FAT_MAGIC_64 {
+                u64::from_be_bytes(arch[8..16].try_into().unwrap())
+            } else {
+                u32::from_be_bytes(arch[8..12].try_into().unwrap()) as u64
+            });
+        }
+        offsets.sort_unstable();
+
+        // Archive entries only read forward, so slices are visited in file order
+        let mut position = 8 + nfat_arch * arch_size as u64;
+        for offset in offsets {
+            let gap = offset.checked_sub(position).ok_or(Error::Parse)?;
+            io::copy(&mut (&mut reader).take(gap), &mut io::sink())?;
+
+            reader.read_exact(&mut magic)?;
+            let (encrypted, read) = read_slice_encryption(magic, &mut reader)?;
+            if encrypted {
+                return Ok(true);
+            }
+            position = offset + read;
+        }
+
+        Ok(false)
+    }
+
+    /// Bytes left for new load commands in each slice, by architecture.
+    pub fn header_padding(&self) -> Result<Vec<(String, usize)>, Error> {
+        self.macho_file
+            .iter_macho()
+            .map(|binary| Ok((arch_name(&binary.macho), binary.header_padding()?)))
+            .collect()
+    }
+
+    pub fn is_universal(&self) -> bool {
+        self.universal
+    }
+
+    /// The slice for `arch`, e.g. `arm64`.
+    pub fn slice(&self, arch: &str) -> Result<&MachOBinary<'_>, Error> {
+        let mut slices = self
+            .macho_file
+            .iter_macho()
+            .filter(|binary| arch_name(&binary.macho) == arch);
+
+        match (slices.next(), slices.next()) {
+            (Some(binary), None) => Ok(binary),
+            (Some(_), Some(_)) => Err(Error::MachOSliceDuplicate(arch.to_string())),
+            (None, _) => Err(Error::MachOSliceMissing(arch.to_string())),
+        }
+    }
+
+    /// Writes the `arch` slice to `output` as a thin binary.
+    pub fn extract_slice<P: AsRef<Path>>(&self, arch: &str, output: P) -> Result<(), Error> {
+        fs::write(output, self.slice(arch)?.data)?;
+        Ok(())
+    }
+
+    /// Drops the slices for `archs`, keeping the file universal like `lipo -remove`.
+    pub fn remove_slices(&mut self, archs: &[&str]) -> Result<(), Error> {
+        for arch in archs {
+            self.slice(arch)?;
+        }
+
+        let remaining = self
+            .macho_file
+            .iter_macho()
+            .filter(|binary| !archs.contains(&arch_name(&binary.macho).as_str()))
+            .map(|binary| binary.data)
+            .collect::<Vec<_>>();
+
+        if remaining.is_empty() {
+            return Err(Error::MachONoSlicesLeft);
+        }
+
+        write_universal(&self.path, &remaining)?;
+
+        *self = Self::new(&self.path)?;
+        Ok(())
+    }
+
+    /// Combines every slice of `binaries` into one universal binary at `output`, like `lipo -create`.
+    pub fn merge<P: AsRef<Path>, O: AsRef<Path>>(binaries: &[P], output: O) -> Result<(), Error> {
+        let machos = binaries
+            .iter()
+            .map(Self::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut archs = Vec::new();
+        let mut slices = Vec::new();
+        for binary in machos.iter().flat_map(|m| m.macho_file.iter_macho()) {
+            let arch = arch_name(&binary.macho);
+            if archs.contains(&arch) {
+                return Err(Error::MachOSliceDuplicate(arch));
+            }
+            archs.push(arch);
+            slices.push(binary.data);
+        }
+
+        write_universal(output.as_ref(), &slices)
+    }
+
     fn extract_entitlements(macho_file: &MachFile<'_>) -> Result<Option<Dictionary>, Error> {
         macho_file.nth_macho(0)?.embedded_entitlements()
     }
 
+    /// Checks the code signature of every slice against the file contents,
+    /// returning a description of each problem found.
+    pub fn verify_signature(&self) -> Result<Vec<String>, Error> {
+        let mut problems = Vec::new();
+
+        for (index, binary) in self.macho_file.iter_macho().enumerate() {
+            if binary.code_signature()?.is_none() {
+                problems.push(format!("slice {index} is not signed"));
+            }
+        }
+
+        if problems.is_empty() {
+            let data = fs::read(&self.path)?;
+            problems.extend(
+                apple_codesign::verify_macho_data(&data)
+                    .into_iter()
+                    .map(|p| p.to_string()),
+            );
+        }
+
+        Ok(problems)
+    }
+
     pub fn app_groups_for_entitlements(&self) -> Option<Vec<String>> {
         self.entitlements
             .as_ref()
@@ -60,23 +280,26 @@ impl MachO {
             })
     }
 
-    // TODO: why is this here again
     pub fn write_changes(&self) -> Result<(), Error> {
-        let mut builder = UniversalBinaryBuilder::default();
-        for binary in self.macho_file.iter_macho() {
-            let _ = builder.add_binary(binary.data);
+        if !self.universal {
+            fs::write(&self.path, self.macho_file.nth_macho(0)?.data)?;
+            return Ok(());
         }
 
-        let writer = &mut fs::File::create(self.path.clone()).map_err(Error::from)?;
-        builder.write(writer)?;
+        let slices = self
+            .macho_file
+            .iter_macho()
+            .map(|binary| binary.data)
+            .collect::<Vec<_>>();
 
-        Ok(())
+        write_universal(&self.path, &slices)
     }
 
-    pub fn add_dylib(&mut self, path: &str) -> Result<(), Error> {
+    /// Adds a load command for `path`, `LC_LOAD_WEAK_DYLIB` when `weak`.
+    pub fn add_dylib(&mut self, path: &str, weak: bool) -> Result<(), Error> {
         let machos = self.macho_file.iter_macho_mut();
         for macho in machos {
-            macho.add_dylib_load_path(path)?;
+            macho.add_dylib_load_path(path, weak)?;
         }
         self.write_changes()?;
         Ok(())
@@ -100,6 +323,40 @@ impl MachO {
         Ok(())
     }
 
+    /// `LC_RPATH` entries of every slice, in load order.
+    pub fn rpaths(&self) -> Result<Vec<String>, Error> {
+        let mut rpaths = Vec::new();
+        for binary in self.macho_file.iter_macho() {
+            for rpath in binary.rpaths()? {
+                if !rpaths.contains(&rpath) {
+                    rpaths.push(rpath);
+                }
+            }
+        }
+
+        Ok(rpaths)
+    }
+
+    /// Removes every `LC_RPATH` for `path`.
+    pub fn remove_rpath(&mut self, path: &str) -> Result<(), Error> {
+        let machos = self.macho_file.iter_macho_mut();
+        for macho in machos {
+            macho.remove_rpath(path)?;
+        }
+        self.write_changes()?;
+        Ok(())
+    }
+
+    /// Adds `LC_RPATH` for `path` to every slice that doesn't have it yet.
+    pub fn add_rpath(&mut self, path: &str) -> Result<(), Error> {
+        let machos = self.macho_file.iter_macho_mut();
+        for macho in machos {
+            macho.add_rpath(path)?;
+        }
+        self.write_changes()?;
+        Ok(())
+    }
+
     pub fn replace_sdk_version(&mut self, new_version: &str) -> Result<(), Error> {
         let machos = self.macho_file.iter_macho_mut();
         for macho in machos {
@@ -110,14 +367,32 @@ impl MachO {
     }
 }
 
+fn write_universal(path: &Path, slices: &[&[u8]]) -> Result<(), Error> {
+    let mut builder = UniversalBinaryBuilder::default();
+    for data in slices {
+        builder.add_binary(data)?;
+    }
+
+    let writer = &mut fs::File::create(path)?;
+    builder.write(writer)?;
+
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub trait MachOExt {
     fn embedded_entitlements(&self) -> Result<Option<Dictionary>, Error>;
     fn dylib_load_paths(&self) -> Result<Vec<String>, Error>;
-    fn add_dylib_load_path(&mut self, path: &str) -> Result<(), Error>;
+    fn dylib_load_commands(&self) -> Result<Vec<DylibLoadCommand>, Error>;
+    fn add_dylib_load_path(&mut self, path: &str, weak: bool) -> Result<(), Error>;
     fn remove_dylib_load_path(&mut self, path: &str) -> Result<(), Error>;
     fn replace_dylib_load_path(&mut self, old_path: &str, new_path: &str) -> Result<(), Error>;
     fn replace_sdk_version(&mut self, new_version: &str) -> Result<(), Error>;
+    fn rpaths(&self) -> Result<Vec<String>, Error>;
+    fn add_rpath(&mut self, path: &str) -> Result<(), Error>;
+    fn remove_rpath(&mut self, path: &str) -> Result<(), Error>;
+    fn is_encrypted(&self) -> Result<bool, Error>;
+    fn header_padding(&self) -> Result<usize, Error>;
 }
 
 // theres multiple binaries in MachFile, being Vec<MachOBinary>
@@ -136,14 +411,6 @@ impl<'a> MachOExt for MachOBinary<'a> {
     }
 
     fn dylib_load_paths(&self) -> Result<Vec<String>, Error> {
-        const DYLIB_COMMANDS: &[u32] = &[
-            LC_LOAD_DYLIB,
-            LC_LOAD_WEAK_DYLIB,
-            LC_REEXPORT_DYLIB,
-            LC_LAZY_LOAD_DYLIB,
-            LC_LOAD_UPWARD_DYLIB,
-        ];
-
         let mut paths = Vec::new();
 
         for load_cmd in &self.macho.load_commands {
@@ -163,9 +430,49 @@ impl<'a> MachOExt for MachOBinary<'a> {
         Ok(paths)
     }
 
+    fn dylib_load_commands(&self) -> Result<Vec<DylibLoadCommand>, Error> {
+        let read_u32_le = |offset: usize| -> Option<u32> {
+            self.data
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let mut commands = Vec::new();
+
+        for load_cmd in &self.macho.load_commands {
+            let cmd = load_cmd.command.cmd();
+            if !DYLIB_COMMANDS.contains(&cmd) {
+                continue;
+            }
+
+            // struct dylib_command { cmd, cmdsize, name, timestamp, current, compatibility }
+            let (Some(path), Some(timestamp), Some(current_version), Some(compatibility_version)) = (
+                manually_parse_dylib(self.data, load_cmd.offset),
+                read_u32_le(load_cmd.offset + 12),
+                read_u32_le(load_cmd.offset + 16),
+                read_u32_le(load_cmd.offset + 20),
+            ) else {
+                continue;
+            };
+
+            commands.push(DylibLoadCommand {
+                cmd,
+                path,
+                timestamp,
+                current_version,
+                compatibility_version,
+            });
+        }
+
+        Ok(commands)
+    }
+
     // these require rewriting the Mach-O
-    fn add_dylib_load_path(&mut self, path: &str) -> Result<(), Error> {
-        let macho = &self.macho;
+    fn add_dylib_load_path(&mut self, path: &str, weak: bool) -> Result<(), Error> {
+        if self.dylib_load_paths()?.iter().any(|p| p == path) {
+            log::warn!("Dylib already exists in binary: {}", path);
+            return Ok(());
+        }
 
         let read_u32_le = |data: &[u8], offset: usize| -> u32 {
             u32::from_le_bytes([
@@ -176,81 +483,23 @@ impl<'a> MachOExt for MachOBinary<'a> {
             ])
         };
 
-        let dylib_exists_in_macho = |macho: &GoblinMachO, base_offset: usize| -> bool {
-            macho.load_commands.iter().any(|load_cmd| {
-                if let CommandVariant::LoadDylib(dylib) = &load_cmd.command {
-                    extract_dylib_path(&self.data, base_offset + load_cmd.offset, dylib.dylib.name)
-                        .map_or(false, |name| name == path)
-                } else {
-                    manually_parse_dylib(&self.data, base_offset + load_cmd.offset)
-                        .map_or(false, |name| name == path)
-                }
-            })
-        };
-
-        let is_64 = matches!(macho.header.cputype, CPU_TYPE_ARM64);
-        let dylib_exists = dylib_exists_in_macho(macho, 0);
-        let current_sizeofcmds = read_u32_le(&self.data, 20);
-        let current_ncmds = read_u32_le(&self.data, 16);
+        // sizeof(dylib_command) = 24, followed by the name, 8-byte aligned
+        let dylib_command_size = (24 + path.len() + 1).next_multiple_of(8);
+        make_room(self, dylib_command_size)?;
 
-        // Create a mutable Vec<u8> so we can modify the Mach-O data in place
         let mut data = self.data.to_vec();
-
-        // with macho.data we can clone, modify, then set the data
-        // for our modified binary, then using MachO struct we can make our universal bin
-
-        if dylib_exists {
-            log::warn!("Dylib already exists in binary: {}", path);
-            return Ok(());
-        }
-
-        let header_size = if is_64 { 32 } else { 28 };
-
-        // Calculate new load command size (must be 8-byte aligned)
-        let dylib_path_len = path.len();
-        let padding = (8 - ((dylib_path_len + 1) % 8)) % 8; // +1 for null terminator
-        let dylib_command_size = 24 + dylib_path_len + 1 + padding; // sizeof(dylib_command) = 24
-
-        // Find the position to insert the new load command
-        let header_offset = 0;
-        let load_commands_offset = header_offset + header_size;
-        let sizeofcmds_offset = header_offset + 20;
-        let ncmds_offset = header_offset + 16;
-
-        // Find the minimum non-zero file offset from segments
-        let min_fileoff = macho
-            .load_commands
-            .iter()
-            .filter_map(|load_cmd| match &load_cmd.command {
-                CommandVariant::Segment64(seg) if seg.filesize > 0 && seg.fileoff > 0 => {
-                    Some(seg.fileoff)
-                }
-                CommandVariant::Segment32(seg) if seg.filesize > 0 && seg.fileoff > 0 => {
-                    Some(seg.fileoff as u64)
-                }
-                _ => None,
-            })
-            .min()
-            .unwrap_or(u64::MAX);
-
-        // Calculate available space
-        let load_commands_end = load_commands_offset + current_sizeofcmds as usize;
-        let data_start = if min_fileoff < u64::MAX {
-            min_fileoff as usize
+        let current_ncmds = read_u32_le(&data, 16);
+        let current_sizeofcmds = read_u32_le(&data, 20);
+        let (load_commands_end, _) = load_command_space(&self.macho, &data);
+
+        let mut new_command = Vec::with_capacity(dylib_command_size);
+        // A weak load lets the app launch without the dylib
+        let cmd = if weak {
+            LC_LOAD_WEAK_DYLIB
         } else {
-            data.len()
+            LC_LOAD_DYLIB
         };
-
-        let available_space = data_start.saturating_sub(load_commands_end);
-
-        if dylib_command_size > available_space {
-            return Err(Error::Parse);
-        }
-
-        // Write the new load command into the available space (no splice needed!)
-        let insert_offset = load_commands_end;
-        let mut new_command = Vec::new();
-        new_command.extend_from_slice(&(LC_LOAD_WEAK_DYLIB as u32).to_le_bytes()); // cmd
+        new_command.extend_from_slice(&cmd.to_le_bytes()); // cmd
         new_command.extend_from_slice(&(dylib_command_size as u32).to_le_bytes()); // cmdsize
 
         // dylib_command structure:
@@ -261,23 +510,20 @@ impl<'a> MachOExt for MachOBinary<'a> {
         //     uint32_t compatibility_version;
         // };
         new_command.extend_from_slice(&24u32.to_le_bytes()); // name.offset (sizeof dylib_command header = 8 + 16 = 24)
-        new_command.extend_from_slice(&2u32.to_le_bytes()); // timestamp
+        new_command.extend_from_slice(&INJECTED_DYLIB_TIMESTAMP.to_le_bytes()); // timestamp
         new_command.extend_from_slice(&0x00010000u32.to_le_bytes()); // current_version (1.0.0)
         new_command.extend_from_slice(&0x00010000u32.to_le_bytes()); // compatibility_version (1.0.0)
         new_command.extend_from_slice(path.as_bytes());
-        new_command.push(0); // null terminator
-        new_command.extend(vec![0u8; padding]); // padding
+        new_command.resize(dylib_command_size, 0); // null terminator and padding
 
         // Write directly into the existing padding space
-        data[insert_offset..insert_offset + dylib_command_size].copy_from_slice(&new_command);
+        data[load_commands_end..load_commands_end + dylib_command_size]
+            .copy_from_slice(&new_command);
 
         // Update header fields
         let new_sizeofcmds = current_sizeofcmds + dylib_command_size as u32;
-        let new_ncmds = current_ncmds + 1;
-
-        data[sizeofcmds_offset..sizeofcmds_offset + 4]
-            .copy_from_slice(&new_sizeofcmds.to_le_bytes());
-        data[ncmds_offset..ncmds_offset + 4].copy_from_slice(&new_ncmds.to_le_bytes());
+        data[20..24].copy_from_slice(&new_sizeofcmds.to_le_bytes());
+        data[16..20].copy_from_slice(&(current_ncmds + 1).to_le_bytes());
 
         self.data = Box::leak(data.into_boxed_slice());
 
@@ -285,7 +531,6 @@ impl<'a> MachOExt for MachOBinary<'a> {
     }
 
     fn remove_dylib_load_path(&mut self, path: &str) -> Result<(), Error> {
-        let macho = &self.macho;
         let mut data = self.data.to_vec();
 
         let read_u32_le = |data: &[u8], offset: usize| -> u32 {
@@ -297,65 +542,32 @@ impl<'a> MachOExt for MachOBinary<'a> {
             ])
         };
 
-        let replacements: Vec<(usize, usize, usize)> = macho
+        let removals: Vec<(usize, usize)> = self
+            .macho
             .load_commands
             .iter()
-            .filter_map(|load_cmd| {
-                match &load_cmd.command {
-                    CommandVariant::LoadDylib(dylib) => {
-                        extract_dylib_path(self.data, load_cmd.offset, dylib.dylib.name)
-                    }
-                    _ => manually_parse_dylib(self.data, load_cmd.offset),
-                }
-                .and_then(|name| {
-                    if name == path {
-                        let cmdsize = read_u32_le(&self.data, load_cmd.offset + 4) as usize;
-                        Some((0, load_cmd.offset, cmdsize))
-                    } else {
-                        None
-                    }
-                })
+            .filter(|load_cmd| DYLIB_COMMANDS.contains(&load_cmd.command.cmd()))
+            .filter(|load_cmd| {
+                manually_parse_dylib(self.data, load_cmd.offset).as_deref() == Some(path)
+            })
+            .map(|load_cmd| {
+                let cmdsize = read_u32_le(self.data, load_cmd.offset + 4) as usize;
+                (load_cmd.offset, cmdsize)
             })
             .collect();
 
-        if replacements.is_empty() {
+        if removals.is_empty() {
             log::warn!("No matching dylib load commands found for path: {}", path);
             return Ok(());
         }
 
-        let current_sizeofcmds = read_u32_le(&self.data, 20);
-        let current_ncmds = read_u32_le(&self.data, 16);
-        let mut total_removed_size = 0;
-        for (arch_offset, cmd_offset, cmdsize) in &replacements {
-            let absolute_cmd_offset = arch_offset + cmd_offset - total_removed_size;
-            // Shift subsequent data to overwrite the removed command
-            data.copy_within(absolute_cmd_offset + cmdsize.., absolute_cmd_offset);
-            total_removed_size += cmdsize;
-        }
-        // Update header fields
-        let new_sizeofcmds = current_sizeofcmds - total_removed_size as u32;
-        let new_ncmds = current_ncmds - replacements.len() as u32;
-        data[20..24].copy_from_slice(&new_sizeofcmds.to_le_bytes());
-        data[16..20].copy_from_slice(&new_ncmds.to_le_bytes());
-        data.truncate(20 + new_sizeofcmds as usize);
-
+        remove_load_commands(&self.macho, &mut data, &removals);
         self.data = Box::leak(data.into_boxed_slice());
 
         Ok(())
     }
 
     fn replace_dylib_load_path(&mut self, old_path: &str, new_path: &str) -> Result<(), Error> {
-        let macho = &self.macho;
-        let mut data = self.data.to_vec();
-
-        const DYLIB_COMMANDS: &[u32] = &[
-            LC_LOAD_DYLIB,
-            LC_LOAD_WEAK_DYLIB,
-            LC_REEXPORT_DYLIB,
-            LC_LAZY_LOAD_DYLIB,
-            LC_LOAD_UPWARD_DYLIB,
-        ];
-
         let read_u32_le = |data: &[u8], offset: usize| -> u32 {
             u32::from_le_bytes([
                 data[offset],
@@ -365,39 +577,32 @@ impl<'a> MachOExt for MachOBinary<'a> {
             ])
         };
 
-        let find_dylib_matches = |macho: &GoblinMachO, base_offset: usize| -> Vec<(usize, usize)> {
-            macho
+        // (offset, cmdsize, name offset) of every command for `old_path`
+        let find_replacements = |binary: &MachOBinary| -> Vec<(usize, usize, usize)> {
+            binary
+                .macho
                 .load_commands
                 .iter()
                 .filter(|load_cmd| DYLIB_COMMANDS.contains(&load_cmd.command.cmd()))
-                .filter_map(|load_cmd| {
-                    // Try to extract the path, first from LoadDylib variant, then manually
-                    let path = match &load_cmd.command {
-                        goblin::mach::load_command::CommandVariant::LoadDylib(dylib) => {
-                            extract_dylib_path(
-                                &self.data,
-                                base_offset + load_cmd.offset,
-                                dylib.dylib.name,
-                            )
-                        }
-                        _ => manually_parse_dylib(&self.data, base_offset + load_cmd.offset),
-                    }?;
-
-                    if path == old_path {
-                        let cmdsize =
-                            read_u32_le(&self.data, base_offset + load_cmd.offset + 4) as usize;
-                        return Some((load_cmd.offset, cmdsize));
-                    }
-                    None
+                .filter(|load_cmd| {
+                    manually_parse_dylib(binary.data, load_cmd.offset).as_deref() == Some(old_path)
+                })
+                .map(|load_cmd| {
+                    (
+                        load_cmd.offset,
+                        read_u32_le(binary.data, load_cmd.offset + 4) as usize,
+                        read_u32_le(binary.data, load_cmd.offset + 8) as usize,
+                    )
                 })
                 .collect()
         };
+        let growth_of = |cmdsize: usize, name_offset: usize| {
+            (name_offset + new_path.len() + 1)
+                .next_multiple_of(8)
+                .saturating_sub(cmdsize)
+        };
 
-        let replacements: Vec<(usize, usize, usize)> = find_dylib_matches(macho, 0)
-            .into_iter()
-            .map(|(offset, size)| (0, offset, size))
-            .collect();
-
+        let replacements = find_replacements(self);
         if replacements.is_empty() {
             log::warn!(
                 "No matching dylib load commands found for path: {}",
@@ -406,33 +611,46 @@ impl<'a> MachOExt for MachOBinary<'a> {
             return Ok(());
         }
 
-        for (arch_offset, cmd_offset, cmdsize) in &replacements {
-            let absolute_cmd_offset = arch_offset + cmd_offset;
-            let dylib_name_offset = absolute_cmd_offset + 24; // sizeof(dylib_command)
-            let available_space = cmdsize - 24;
-
-            let new_path_len = new_path.len();
-            let old_path_len = old_path.len();
-            let new_padding = (8 - ((new_path_len + 1) % 8)) % 8;
-            let required_space = new_path_len + 1 + new_padding;
-
-            if required_space > available_space {
-                return Err(Error::Parse);
-            }
+        let needed = replacements
+            .iter()
+            .map(|(_, cmdsize, name_offset)| growth_of(*cmdsize, *name_offset))
+            .sum();
+        // Reclaiming space moves commands around, look them up again after
+        let replacements = match make_room(self, needed)? {
+            true => find_replacements(self),
+            false => replacements,
+        };
 
-            // Only zero out the space we're actually using (old path + its null terminator + old padding)
-            let old_padding = (8 - ((old_path_len + 1) % 8)) % 8;
-            let old_total_size = old_path_len + 1 + old_padding;
-            for i in 0..old_total_size.min(available_space) {
-                data[dylib_name_offset + i] = 0;
+        let mut data = self.data.to_vec();
+        let mut sizeofcmds = read_u32_le(&data, 20);
+        let (mut load_commands_end, _) = load_command_space(&self.macho, &data);
+
+        // Last one first, growing a command only moves the commands after it
+        for (cmd_offset, cmdsize, name_offset) in replacements.iter().rev() {
+            let mut cmdsize = *cmdsize;
+
+            let growth = growth_of(cmdsize, *name_offset);
+            if growth > 0 {
+                let new_cmdsize = cmdsize + growth;
+                data.copy_within(
+                    cmd_offset + cmdsize..load_commands_end,
+                    cmd_offset + new_cmdsize,
+                );
+                data[cmd_offset + 4..cmd_offset + 8]
+                    .copy_from_slice(&(new_cmdsize as u32).to_le_bytes());
+
+                cmdsize = new_cmdsize;
+                sizeofcmds += growth as u32;
+                load_commands_end += growth;
             }
 
-            // Write new path and null terminator
-            data[dylib_name_offset..dylib_name_offset + new_path_len]
-                .copy_from_slice(new_path.as_bytes());
-            // Null terminator is already written by the zeroing above, padding bytes are also zeros
+            let name_start = cmd_offset + name_offset;
+            data[name_start..cmd_offset + cmdsize].fill(0);
+            data[name_start..name_start + new_path.len()].copy_from_slice(new_path.as_bytes());
         }
 
+        data[20..24].copy_from_slice(&sizeofcmds.to_le_bytes());
+
         self.data = Box::leak(data.into_boxed_slice());
 
         Ok(())
@@ -468,6 +686,284 @@ impl<'a> MachOExt for MachOBinary<'a> {
 
         Ok(())
     }
+
+    fn is_encrypted(&self) -> Result<bool, Error> {
+        // `cryptid` is cleared once a decrypted binary is dumped from a device
+        Ok(self
+            .macho
+            .load_commands
+            .iter()
+            .any(|load_cmd| match &load_cmd.command {
+                CommandVariant::EncryptionInfo32(info) => info.cryptid != 0,
+                CommandVariant::EncryptionInfo64(info) => info.cryptid != 0,
+                _ => false,
+            }))
+    }
+
+    fn header_padding(&self) -> Result<usize, Error> {
+        Ok(usable_space(&self.macho, self.data))
+    }
+
+    fn rpaths(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .macho
+            .load_commands
+            .iter()
+            .filter_map(|load_cmd| match &load_cmd.command {
+                CommandVariant::Rpath(rpath) => {
+                    extract_dylib_path(self.data, load_cmd.offset, rpath.path)
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn remove_rpath(&mut self, path: &str) -> Result<(), Error> {
+        let removals = self
+            .macho
+            .load_commands
+            .iter()
+            .filter(|load_cmd| match &load_cmd.command {
+                CommandVariant::Rpath(rpath) => {
+                    extract_dylib_path(self.data, load_cmd.offset, rpath.path).as_deref()
+                        == Some(path)
+                }
+                _ => false,
+            })
+            .map(|load_cmd| (load_cmd.offset, load_cmd.command.cmdsize()))
+            .collect::<Vec<_>>();
+
+        if removals.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = self.data.to_vec();
+        remove_load_commands(&self.macho, &mut data, &removals);
+        self.data = Box::leak(data.into_boxed_slice());
+
+        Ok(())
+    }
+
+    fn add_rpath(&mut self, path: &str) -> Result<(), Error> {
+        if self.rpaths()?.iter().any(|p| p == path) {
+            return Ok(());
+        }
+
+        let read_u32_le = |data: &[u8], offset: usize| -> u32 {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+
+        // struct rpath_command { cmd, cmdsize, path.offset } followed by the string, 8-byte aligned
+        let rpath_command_size = (12 + path.len() + 1).next_multiple_of(8);
+        make_room(self, rpath_command_size)?;
+
+        let mut data = self.data.to_vec();
+        let current_ncmds = read_u32_le(&data, 16);
+        let current_sizeofcmds = read_u32_le(&data, 20);
+        let (load_commands_end, _) = load_command_space(&self.macho, &data);
+
+        let mut new_command = Vec::with_capacity(rpath_command_size);
+        new_command.extend_from_slice(&LC_RPATH.to_le_bytes());
+        new_command.extend_from_slice(&(rpath_command_size as u32).to_le_bytes());
+        new_command.extend_from_slice(&12u32.to_le_bytes());
+        new_command.extend_from_slice(path.as_bytes());
+        new_command.resize(rpath_command_size, 0);
+
+        data[load_commands_end..load_commands_end + rpath_command_size]
+            .copy_from_slice(&new_command);
+
+        let new_sizeofcmds = current_sizeofcmds + rpath_command_size as u32;
+        data[20..24].copy_from_slice(&new_sizeofcmds.to_le_bytes());
+        data[16..20].copy_from_slice(&(current_ncmds + 1).to_le_bytes());
+
+        self.data = Box::leak(data.into_boxed_slice());
+
+        Ok(())
+    }
+}
+
+// Free header space once signing has room for its LC_CODE_SIGNATURE
+fn usable_space(macho: &GoblinMachO, data: &[u8]) -> usize {
+    let (_, available) = load_command_space(macho, data);
+    let has_signature = macho
+        .load_commands
+        .iter()
+        .any(|load_cmd| load_cmd.command.cmd() == LC_CODE_SIGNATURE);
+
+    match has_signature {
+        true => available,
+        false => available.saturating_sub(CODE_SIGNATURE_COMMAND_SIZE),
+    }
+}
+
+/// Makes sure `needed` more bytes of load commands fit in the header, dropping
+/// commands nothing reads at runtime if they don't. Returns whether commands moved.
+///
+/// Sections can't be moved without relinking, so the header never grows past
+/// the first one.
+fn make_room(binary: &mut MachOBinary<'_>, needed: usize) -> Result<bool, Error> {
+    let available = usable_space(&binary.macho, binary.data);
+    if needed <= available {
+        return Ok(false);
+    }
+
+    // Code signing DRs are legacy linker output and repeated rpaths are searched once
+    let mut seen_rpaths = Vec::new();
+    let mut reclaimable = Vec::new();
+    for load_cmd in &binary.macho.load_commands {
+        let reclaim = match &load_cmd.command {
+            CommandVariant::Rpath(rpath) => {
+                let path = extract_dylib_path(binary.data, load_cmd.offset, rpath.path);
+                let repeated = seen_rpaths.contains(&path);
+                seen_rpaths.push(path);
+                repeated
+            }
+            command => command.cmd() == LC_DYLIB_CODE_SIGN_DRS,
+        };
+        if reclaim {
+            reclaimable.push((load_cmd.offset, load_cmd.command.cmdsize()));
+        }
+    }
+
+    let reclaimed: usize = reclaimable.iter().map(|(_, cmdsize)| cmdsize).sum();
+    if needed > available + reclaimed {
+        return Err(Error::MachOHeaderFull {
+            arch: arch_name(&binary.macho),
+            needed,
+            available,
+        });
+    }
+
+    log::info!(
+        "Reclaiming {} bytes of load commands in the {} slice",
+        reclaimed,
+        arch_name(&binary.macho)
+    );
+    let mut data = binary.data.to_vec();
+    remove_load_commands(&binary.macho, &mut data, &reclaimable);
+
+    let data: &'static [u8] = Box::leak(data.into_boxed_slice());
+    binary.macho = GoblinMachO::parse(data, 0).map_err(|_| Error::Parse)?;
+    binary.data = data;
+
+    Ok(true)
+}
+
+// Whether the slice starting with `magic` has a set `cryptid`, and how many bytes of it
+// were read. Reads the header and load commands only.
+fn read_slice_encryption<R: Read>(magic: [u8; 4], reader: &mut R) -> Result<(bool, u64), Error> {
+    let header_size = match u32::from_le_bytes(magic) {
+        MH_MAGIC => 28,
+        MH_MAGIC_64 => 32,
+        // Big endian slices don't run on iOS
+        _ => return Ok((false, 4)),
+    };
+
+    let mut header = vec![0u8; header_size];
+    header[..4].copy_from_slice(&magic);
+    reader.read_exact(&mut header[4..])?;
+    let ncmds = u32::from_le_bytes(header[16..20].try_into().unwrap());
+    let sizeofcmds = u32::from_le_bytes(header[20..24].try_into().unwrap()) as u64;
+
+    let mut commands = Vec::new();
+    reader.take(sizeofcmds).read_to_end(&mut commands)?;
+    if commands.len() as u64 != sizeofcmds {
+        return Err(Error::Parse);
+    }
+
+    let read_u32_le = |offset: usize| -> Option<u32> {
+        commands
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    // encryption_info_command: cmd, cmdsize, cryptoff, cryptsize, cryptid
+    let mut offset = 0;
+    let mut encrypted = false;
+    for _ in 0..ncmds {
+        let (Some(cmd), Some(cmdsize)) = (read_u32_le(offset), read_u32_le(offset + 4)) else {
+            break;
+        };
+        if (cmd == LC_ENCRYPTION_INFO || cmd == LC_ENCRYPTION_INFO_64)
+            && read_u32_le(offset + 16).is_some_and(|cryptid| cryptid != 0)
+        {
+            encrypted = true;
+            break;
+        }
+        if cmdsize < 8 {
+            break;
+        }
+        offset += cmdsize as usize;
+    }
+
+    Ok((encrypted, header_size as u64 + sizeofcmds))
+}
+
+// Removes `(offset, cmdsize)` commands, sorted by offset, and updates the header
+fn remove_load_commands(macho: &GoblinMachO, data: &mut [u8], removals: &[(usize, usize)]) {
+    let current_ncmds = u32::from_le_bytes([data[16], data[17], data[18], data[19]]);
+    let current_sizeofcmds = u32::from_le_bytes([data[20], data[21], data[22], data[23]]);
+    let (load_commands_end, _) = load_command_space(macho, data);
+
+    // Only the load commands move, last one first so earlier offsets stay valid
+    let mut total_removed_size = 0;
+    for (cmd_offset, cmdsize) in removals.iter().rev() {
+        data.copy_within(cmd_offset + cmdsize..load_commands_end, *cmd_offset);
+        total_removed_size += cmdsize;
+    }
+    data[load_commands_end - total_removed_size..load_commands_end].fill(0);
+
+    // Update header fields
+    let new_sizeofcmds = current_sizeofcmds - total_removed_size as u32;
+    let new_ncmds = current_ncmds - removals.len() as u32;
+    data[20..24].copy_from_slice(&new_sizeofcmds.to_le_bytes());
+    data[16..20].copy_from_slice(&new_ncmds.to_le_bytes());
+}
+
+fn arch_name(macho: &GoblinMachO) -> String {
+    let (cputype, cpusubtype) = (macho.header.cputype(), macho.header.cpusubtype());
+    get_arch_name_from_types(cputype, cpusubtype)
+        .map(String::from)
+        .unwrap_or_else(|| format!("cpu {}:{}", cputype, cpusubtype))
+}
+
+// End of the load commands and the free space between them and the first section
+fn load_command_space(macho: &GoblinMachO, data: &[u8]) -> (usize, usize) {
+    let header_size = if macho.is_64 { 32 } else { 28 };
+    let sizeofcmds = u32::from_le_bytes([data[20], data[21], data[22], data[23]]) as usize;
+    let load_commands_end = header_size + sizeofcmds;
+
+    // __TEXT starts at 0, so its first section is what bounds the header
+    let first_section = macho
+        .segments
+        .iter()
+        .filter_map(|segment| segment.sections().ok())
+        .flatten()
+        .filter(|(section, _)| section.offset > 0 && section.size > 0)
+        .map(|(section, _)| section.offset as usize)
+        .min();
+    let first_segment = macho
+        .segments
+        .iter()
+        .filter(|segment| segment.fileoff > 0 && segment.filesize > 0)
+        .map(|segment| segment.fileoff as usize)
+        .min();
+
+    let data_start = first_section
+        .into_iter()
+        .chain(first_segment)
+        .min()
+        .unwrap_or(data.len());
+
+    (
+        load_commands_end,
+        data_start.saturating_sub(load_commands_end),
+    )
 }
 
 fn extract_dylib_path(
@@ -505,3 +1001,290 @@ fn manually_parse_dylib(file_data: &[u8], load_cmd_offset: usize) -> Option<Stri
 
     extract_dylib_path(file_data, load_cmd_offset, name_offset_field)
 }
+
+#[cfg(test)]
+mod tests {
+    use goblin::mach::load_command::LC_SEGMENT_64;
+
+    use super::*;
+
+    // segment_command_64 with one section_64
+    const SEGMENT_COMMAND_SIZE: usize = 72 + 80;
+    const FILE_SIZE: usize = 0x1000;
+    const FRAMEWORKS: &str = "@executable_path/Frameworks";
+
+    fn string_command(cmd: u32, fields: &[u32], string: &str) -> Vec<u8> {
+        let cmdsize = (8 + fields.len() * 4 + string.len() + 1).next_multiple_of(8);
+        let mut command = Vec::with_capacity(cmdsize);
+        command.extend_from_slice(&cmd.to_le_bytes());
+        command.extend_from_slice(&(cmdsize as u32).to_le_bytes());
+        for field in fields {
+            command.extend_from_slice(&field.to_le_bytes());
+        }
+        command.extend_from_slice(string.as_bytes());
+        command.resize(cmdsize, 0);
+        command
+    }
+
+    fn rpath(path: &str) -> Vec<u8> {
+        string_command(LC_RPATH, &[12], path)
+    }
+
+    fn dylib(path: &str) -> Vec<u8> {
+        string_command(LC_LOAD_DYLIB, &[24, 2, 0x10000, 0x10000], path)
+    }
+
+    fn code_sign_drs() -> Vec<u8> {
+        [LC_DYLIB_CODE_SIGN_DRS, 16, 0, 0]
+            .iter()
+            .flat_map(|field| field.to_le_bytes())
+            .collect()
+    }
+
+    fn name(name: &str) -> [u8; 16] {
+        let mut field = [0u8; 16];
+        field[..name.len()].copy_from_slice(name.as_bytes());
+        field
+    }
+
+    // A thin arm64 executable, `__TEXT` maps the whole file and its one section
+    // starts at `section_offset`
+    fn fixture(commands: &[Vec<u8>], section_offset: usize) -> MachFile<'static> {
+        let sizeofcmds = SEGMENT_COMMAND_SIZE + commands.iter().map(Vec::len).sum::<usize>();
+        let ncmds = commands.len() as u32 + 1;
+
+        let mut data = Vec::with_capacity(FILE_SIZE);
+        for field in [
+            MH_MAGIC_64,
+            0x0100_000c,
+            0,
+            2,
+            ncmds,
+            sizeofcmds as u32,
+            0,
+            0,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        data.extend_from_slice(&LC_SEGMENT_64.to_le_bytes());
+        data.extend_from_slice(&(SEGMENT_COMMAND_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&name("__TEXT"));
+        for field in [0x1_0000_0000, FILE_SIZE as u64, 0, FILE_SIZE as u64] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [5u32, 5, 1, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        data.extend_from_slice(&name("__text"));
+        data.extend_from_slice(&name("__TEXT"));
+        for field in [0x1_0000_0000 + section_offset as u64, 0x10] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [section_offset as u32, 2, 0, 0, 0x8000_0400, 0, 0, 0] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+
+        for command in commands {
+            data.extend_from_slice(command);
+        }
+        assert!(data.len() <= section_offset);
+        data.resize(FILE_SIZE, 0);
+
+        MachFile::parse(Box::leak(data.into_boxed_slice())).unwrap()
+    }
+
+    // Commands changed through `data` only show up once parsed again
+    fn reparse(data: &'static [u8]) -> MachFile<'static> {
+        MachFile::parse(data).unwrap()
+    }
+
+    fn header_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn load_command_space_ends_at_first_section() {
+        let file = fixture(&[dylib("@rpath/a.dylib")], 0x400);
+        let binary = file.iter_macho().next().unwrap();
+
+        let load_commands_end = 32 + SEGMENT_COMMAND_SIZE + 40;
+        assert_eq!(
+            load_command_space(&binary.macho, binary.data),
+            (load_commands_end, 0x400 - load_commands_end)
+        );
+        // Signing still has to add its LC_CODE_SIGNATURE
+        assert_eq!(
+            usable_space(&binary.macho, binary.data),
+            0x400 - load_commands_end - CODE_SIGNATURE_COMMAND_SIZE
+        );
+    }
+
+    #[test]
+    fn remove_load_commands_moves_later_commands_up() {
+        let removed = rpath("@loader_path");
+        let file = fixture(
+            &[removed.clone(), dylib("@rpath/a.dylib"), rpath(FRAMEWORKS)],
+            0x400,
+        );
+        let binary = file.iter_macho().next().unwrap();
+
+        let offset = 32 + SEGMENT_COMMAND_SIZE;
+        let (load_commands_end, _) = load_command_space(&binary.macho, binary.data);
+        let mut data = binary.data.to_vec();
+        remove_load_commands(&binary.macho, &mut data, &[(offset, removed.len())]);
+
+        assert_eq!(header_u32(&data, 16), 3);
+        assert_eq!(
+            header_u32(&data, 20) as usize,
+            load_commands_end - 32 - removed.len()
+        );
+        assert_eq!(data[offset..offset + 40], dylib("@rpath/a.dylib"));
+        assert!(
+            data[load_commands_end - removed.len()..load_commands_end]
+                .iter()
+                .all(|&b| b == 0)
+        );
+
+        let file = reparse(Box::leak(data.into_boxed_slice()));
+        let binary = file.iter_macho().next().unwrap();
+        assert_eq!(binary.rpaths().unwrap(), [FRAMEWORKS]);
+        assert_eq!(binary.dylib_load_paths().unwrap(), ["@rpath/a.dylib"]);
+    }
+
+    // Segment, a repeated rpath, DRs and a dylib, leaving 8 usable bytes
+    fn tight_fixture() -> MachFile<'static> {
+        let commands = [
+            rpath(FRAMEWORKS),
+            rpath(FRAMEWORKS),
+            code_sign_drs(),
+            dylib("@rpath/a.dylib"),
+        ];
+        let load_commands_end = 32 + SEGMENT_COMMAND_SIZE + 40 + 40 + 16 + 40;
+        fixture(
+            &commands,
+            load_commands_end + CODE_SIGNATURE_COMMAND_SIZE + 8,
+        )
+    }
+
+    #[test]
+    fn make_room_leaves_commands_when_they_fit() {
+        let mut file = tight_fixture();
+        let binary = file.iter_macho_mut().next().unwrap();
+        let data = binary.data;
+
+        assert!(!make_room(binary, 8).unwrap());
+        assert_eq!(binary.data, data);
+    }
+
+    #[test]
+    fn make_room_reclaims_repeated_rpaths_and_code_sign_drs() {
+        let mut file = tight_fixture();
+        let binary = file.iter_macho_mut().next().unwrap();
+
+        assert!(make_room(binary, 8 + 40 + 16).unwrap());
+        assert_eq!(usable_space(&binary.macho, binary.data), 8 + 40 + 16);
+        assert_eq!(binary.rpaths().unwrap(), [FRAMEWORKS]);
+        assert_eq!(binary.dylib_load_paths().unwrap(), ["@rpath/a.dylib"]);
+        assert!(
+            !binary
+                .macho
+                .load_commands
+                .iter()
+                .any(|load_cmd| load_cmd.command.cmd() == LC_DYLIB_CODE_SIGN_DRS)
+        );
+    }
+
+    #[test]
+    fn make_room_fails_when_reclaiming_is_not_enough() {
+        let mut file = tight_fixture();
+        let binary = file.iter_macho_mut().next().unwrap();
+        let data = binary.data;
+
+        assert!(matches!(
+            make_room(binary, 8 + 40 + 16 + 1),
+            Err(Error::MachOHeaderFull {
+                needed: 65,
+                available: 8,
+                ..
+            })
+        ));
+        assert_eq!(binary.data, data);
+    }
+
+    #[test]
+    fn replace_dylib_load_path_grows_command_in_place() {
+        let mut file = fixture(
+            &[dylib("@rpath/a.dylib"), dylib("/usr/lib/libz.1.dylib")],
+            0x400,
+        );
+        let binary = file.iter_macho_mut().next().unwrap();
+        let sizeofcmds = header_u32(binary.data, 20);
+
+        // 24 + 42 bytes, padded to 72 from 40
+        let new_path = "@rpath/Frameworks/Longer.framework/Longer";
+        binary
+            .replace_dylib_load_path("@rpath/a.dylib", new_path)
+            .unwrap();
+
+        let offset = 32 + SEGMENT_COMMAND_SIZE;
+        assert_eq!(header_u32(binary.data, 20), sizeofcmds + 32);
+        assert_eq!(header_u32(binary.data, offset + 4), 72);
+
+        let file = reparse(binary.data);
+        let binary = file.iter_macho().next().unwrap();
+        assert_eq!(
+            binary.dylib_load_paths().unwrap(),
+            [new_path, "/usr/lib/libz.1.dylib"]
+        );
+    }
+
+    #[test]
+    fn replace_dylib_load_path_keeps_size_when_shorter() {
+        let mut file = fixture(&[dylib("@rpath/a.dylib")], 0x400);
+        let binary = file.iter_macho_mut().next().unwrap();
+        let sizeofcmds = header_u32(binary.data, 20);
+
+        binary
+            .replace_dylib_load_path("@rpath/a.dylib", "@rpath/b")
+            .unwrap();
+
+        assert_eq!(header_u32(binary.data, 20), sizeofcmds);
+        let file = reparse(binary.data);
+        let binary = file.iter_macho().next().unwrap();
+        assert_eq!(binary.dylib_load_paths().unwrap(), ["@rpath/b"]);
+    }
+
+    #[test]
+    fn replace_dylib_load_path_reclaims_space_to_grow() {
+        let mut file = tight_fixture();
+        let binary = file.iter_macho_mut().next().unwrap();
+        let sizeofcmds = header_u32(binary.data, 20);
+
+        // Grows by 32, more than the 8 usable bytes
+        let new_path = "@rpath/Frameworks/Longer.framework/Longer";
+        binary
+            .replace_dylib_load_path("@rpath/a.dylib", new_path)
+            .unwrap();
+
+        assert_eq!(header_u32(binary.data, 20), sizeofcmds - 40 - 16 + 32);
+        let file = reparse(binary.data);
+        let binary = file.iter_macho().next().unwrap();
+        assert_eq!(binary.dylib_load_paths().unwrap(), [new_path]);
+        assert_eq!(binary.rpaths().unwrap(), [FRAMEWORKS]);
+    }
+
+    #[test]
+    fn replace_dylib_load_path_fails_without_room() {
+        let mut file = tight_fixture();
+        let binary = file.iter_macho_mut().next().unwrap();
+
+        // 24 + 84 bytes, padded to 112, 72 more than the command has
+        let new_path = format!("@rpath/{}.dylib", "x".repeat(70));
+        assert!(matches!(
+            binary.replace_dylib_load_path("@rpath/a.dylib", &new_path),
+            Err(Error::MachOHeaderFull { needed: 72, .. })
+        ));
+    }
+}
@@ -7,7 +7,7 @@ mod provision;
 
 pub use certificate::CertificateIdentity;
 #[cfg(feature = "tweaks")]
-pub use macho::{MachO, MachOExt};
+pub use macho::{DylibLoadCommand, MachO, MachOExt};
 pub use provision::MobileProvision;
 
 pub const TEAM_ID_REGEX: &str = r"^[A-Z0-9]{10}\.";
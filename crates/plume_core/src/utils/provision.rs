@@ -1,17 +1,57 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use crate::Error;
 use crate::utils::TEAM_ID_REGEX;
-use plist::{Date, Dictionary, Value};
+use cryptographic_message_syntax::SignedData;
+use plist::{Data, Date, Dictionary, Value};
+use serde::Deserialize;
+use x509_certificate::CapturedX509Certificate;
 
 use super::MachO;
 
 #[derive(Clone)]
 pub struct MobileProvision {
     pub data: Vec<u8>,
+    name: String,
+    uuid: String,
+    team_identifiers: Vec<String>,
+    team_name: Option<String>,
+    app_id_name: Option<String>,
+    platforms: Vec<String>,
+    creation_date: Option<Date>,
+    expiration_date: Date,
+    provisioned_devices: Vec<String>,
+    provisions_all_devices: bool,
+    developer_certificates: Vec<CapturedX509Certificate>,
     entitlements: Dictionary,
+}
+
+// Keys of the plist embedded in the profile's CMS envelope
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ProfilePlist {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "UUID", default)]
+    uuid: String,
+    #[serde(default)]
+    team_identifier: Vec<String>,
+    team_name: Option<String>,
+    #[serde(rename = "AppIDName")]
+    app_id_name: Option<String>,
+    #[serde(default)]
+    platform: Vec<String>,
+    creation_date: Option<Date>,
     expiration_date: Date,
+    #[serde(default)]
+    provisioned_devices: Vec<String>,
+    #[serde(default)]
+    provisions_all_devices: bool,
+    #[serde(default)]
+    developer_certificates: Vec<Data>,
+    entitlements: Option<Dictionary>,
 }
 
 impl MobileProvision {
@@ -23,12 +63,31 @@ impl MobileProvision {
     }
 
     pub fn load_with_bytes(data: Vec<u8>) -> Result<Self, Error> {
-        let (entitlements, expiration_date) = Self::extract_entitlements_from_prov(&data)?;
+        let content = Self::verify_signed_content(&data)?;
+        let profile: ProfilePlist = plist::from_bytes(&content)?;
+
+        let developer_certificates = profile
+            .developer_certificates
+            .into_iter()
+            .map(|c| CapturedX509Certificate::from_der(Vec::from(c)))
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
             data,
-            entitlements,
-            expiration_date,
+            name: profile.name,
+            uuid: profile.uuid,
+            team_identifiers: profile.team_identifier,
+            team_name: profile.team_name,
+            app_id_name: profile.app_id_name,
+            platforms: profile.platform,
+            creation_date: profile.creation_date,
+            expiration_date: profile.expiration_date,
+            provisioned_devices: profile.provisioned_devices,
+            provisions_all_devices: profile.provisions_all_devices,
+            developer_certificates,
+            entitlements: profile
+                .entitlements
+                .ok_or(Error::ProvisioningEntitlementsUnknown)?,
         })
     }
 
@@ -67,6 +126,88 @@ impl MobileProvision {
         &self.expiration_date
     }
 
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn uuid(&self) -> &String {
+        &self.uuid
+    }
+
+    pub fn team_identifiers(&self) -> &Vec<String> {
+        &self.team_identifiers
+    }
+
+    pub fn team_name(&self) -> Option<&String> {
+        self.team_name.as_ref()
+    }
+
+    pub fn app_id_name(&self) -> Option<&String> {
+        self.app_id_name.as_ref()
+    }
+
+    pub fn platforms(&self) -> &Vec<String> {
+        &self.platforms
+    }
+
+    pub fn creation_date(&self) -> Option<&Date> {
+        self.creation_date.as_ref()
+    }
+
+    pub fn provisioned_devices(&self) -> &Vec<String> {
+        &self.provisioned_devices
+    }
+
+    pub fn provisions_all_devices(&self) -> bool {
+        self.provisions_all_devices
+    }
+
+    pub fn developer_certificates(&self) -> &Vec<CapturedX509Certificate> {
+        &self.developer_certificates
+    }
+
+    /// Checks the profile can actually be used to sign with `certificate` and
+    /// install onto `udid`, so mismatches don't surface as install failures.
+    pub fn verify_compatibility(
+        &self,
+        certificate: Option<&CapturedX509Certificate>,
+        udid: Option<&str>,
+    ) -> Result<(), Error> {
+        if SystemTime::from(self.expiration_date) <= SystemTime::now() {
+            return Err(Error::ProvisioningExpired(self.name.clone()));
+        }
+
+        if let Some(certificate) = certificate {
+            if !self
+                .developer_certificates
+                .iter()
+                .any(|c| c.constructed_data() == certificate.constructed_data())
+            {
+                return Err(Error::ProvisioningCertificateMissing(self.name.clone()));
+            }
+        }
+
+        if let Some(udid) = udid {
+            if !self.provisions_device(udid) {
+                return Err(Error::ProvisioningDeviceMissing {
+                    profile: self.name.clone(),
+                    udid: udid.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a device with this UDID may run apps signed with the profile.
+    pub fn provisions_device(&self, udid: &str) -> bool {
+        self.provisions_all_devices
+            || self
+                .provisioned_devices
+                .iter()
+                .any(|d| d.eq_ignore_ascii_case(udid))
+    }
+
     pub fn entitlements_as_bytes(&self) -> Result<Vec<u8>, Error> {
         let mut buf = Vec::new();
         Value::Dictionary(self.entitlements.clone()).to_writer_xml(&mut buf)?;
@@ -85,34 +226,30 @@ impl MobileProvision {
         Some(bundle_id)
     }
 
-    fn extract_entitlements_from_prov(data: &[u8]) -> Result<(Dictionary, Date), Error> {
-        let start = data
-            .windows(6)
-            .position(|w| w == b"<plist")
-            .ok_or(Error::ProvisioningEntitlementsUnknown)?;
-        let end = data
-            .windows(8)
-            .rposition(|w| w == b"</plist>")
-            .ok_or(Error::ProvisioningEntitlementsUnknown)?
-            + 8;
-        let plist_data = &data[start..end];
-        let plist = plist::Value::from_reader_xml(plist_data)?;
-
-        let expiration_date = plist
-            .as_dictionary()
-            .and_then(|d| d.get("ExpirationDate"))
-            .and_then(|v| v.as_date());
-
-        let entitlements = plist
-            .as_dictionary()
-            .and_then(|d| d.get("Entitlements"))
-            .and_then(|v| v.as_dictionary())
-            .cloned()
-            .ok_or(Error::ProvisioningEntitlementsUnknown);
-
-        Ok((
-            entitlements?,
-            expiration_date.ok_or(Error::ProvisioningEntitlementsUnknown)?,
-        ))
+    // Profiles are CMS SignedData with the plist as content, make sure
+    // every signer actually signed what we're about to trust
+    fn verify_signed_content(data: &[u8]) -> Result<Vec<u8>, Error> {
+        let signed_data =
+            SignedData::parse_ber(data).map_err(|e| Error::ProvisioningSignature(e.to_string()))?;
+
+        let content = signed_data
+            .signed_content()
+            .ok_or_else(|| Error::ProvisioningSignature("no signed content".into()))?;
+
+        let mut signers = signed_data.signers().peekable();
+        if signers.peek().is_none() {
+            return Err(Error::ProvisioningSignature("no signers".into()));
+        }
+
+        for signer in signers {
+            signer
+                .verify_signature_with_signed_data(&signed_data)
+                .map_err(|e| Error::ProvisioningSignature(e.to_string()))?;
+            signer
+                .verify_message_digest_with_signed_data(&signed_data)
+                .map_err(|e| Error::ProvisioningSignature(e.to_string()))?;
+        }
+
+        Ok(content.to_vec())
     }
 }
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Free developer accounts may only create this many App IDs...
+pub const FREE_APP_ID_LIMIT: usize = 10;
+/// ...within this rolling window.
+pub const FREE_APP_ID_WINDOW_DAYS: i64 = 7;
+
+// App IDs plume created, so we know which ones are safe to reuse
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegisteredAppId {
+    pub team_id: String,
+    pub app_id_id: String,
+    pub identifier: String,
+    pub created: DateTime<Utc>,
+}
+
+impl RegisteredAppId {
+    pub fn new(team_id: String, app_id_id: String, identifier: String) -> Self {
+        Self {
+            team_id,
+            app_id_id,
+            identifier,
+            created: Utc::now(),
+        }
+    }
+
+    /// Whether this App ID still counts towards the rolling quota.
+    pub fn counts_towards_quota(&self, now: DateTime<Utc>) -> bool {
+        now - self.created < Duration::days(FREE_APP_ID_WINDOW_DAYS)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AppIdQuota {
+    pub limit: usize,
+    pub used: usize,
+    /// When the oldest App ID inside the window stops counting, if any.
+    pub next_slot: Option<DateTime<Utc>>,
+}
+
+impl AppIdQuota {
+    pub fn remaining(&self) -> usize {
+        self.limit.saturating_sub(self.used)
+    }
+}
+
+impl std::fmt::Display for AppIdQuota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} App IDs left", self.remaining(), self.limit)?;
+        if let Some(next_slot) = self.next_slot {
+            write!(
+                f,
+                " (next slot frees up {})",
+                next_slot.format("%Y-%m-%d %H:%M UTC")
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
+mod app_ids;
 mod gsa_account;
 mod refresh;
 mod store;
+pub use app_ids::{AppIdQuota, FREE_APP_ID_LIMIT, FREE_APP_ID_WINDOW_DAYS, RegisteredAppId};
 pub use gsa_account::{GsaAccount, account_from_session};
 pub use refresh::{RefreshApp, RefreshDevice};
 pub use store::AccountStore;
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use plume_core::Error;
 
-use crate::{GsaAccount, RefreshDevice};
+use crate::{AppIdQuota, FREE_APP_ID_LIMIT, GsaAccount, RefreshDevice, RegisteredAppId};
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
 pub struct AccountStore {
@@ -13,6 +13,8 @@ pub struct AccountStore {
     accounts: HashMap<String, GsaAccount>, // Email -> GsaAccount
     #[serde(default)]
     refreshes: HashMap<String, RefreshDevice>, // UDID -> RefreshDevice (apps?)
+    #[serde(default)]
+    app_ids: Vec<RegisteredAppId>, // App IDs created by plume
     #[serde(skip)]
     path: Option<PathBuf>,
 }
@@ -213,4 +215,80 @@ impl AccountStore {
         self.refreshes.remove(udid);
         self.save_sync()
     }
+
+    pub fn app_ids(&self, team_id: &str) -> Vec<&RegisteredAppId> {
+        self.app_ids
+            .iter()
+            .filter(|a| a.team_id == team_id)
+            .collect()
+    }
+
+    pub fn app_id_quota(&self, team_id: &str) -> AppIdQuota {
+        let now = chrono::Utc::now();
+        let counted = self
+            .app_ids(team_id)
+            .into_iter()
+            .filter(|a| a.counts_towards_quota(now))
+            .collect::<Vec<_>>();
+
+        AppIdQuota {
+            limit: FREE_APP_ID_LIMIT,
+            used: counted.len(),
+            next_slot: counted
+                .iter()
+                .map(|a| a.created + chrono::Duration::days(crate::FREE_APP_ID_WINDOW_DAYS))
+                .min(),
+        }
+    }
+
+    /// Plume-created App IDs still counting towards the quota that aren't in
+    /// `in_use` and aren't kept alive by an auto-refresh entry, oldest first.
+    pub fn stale_app_ids(&self, team_id: &str, in_use: &[String]) -> Vec<RegisteredAppId> {
+        let now = chrono::Utc::now();
+        let refreshed = self
+            .refreshes
+            .values()
+            .flat_map(|d| d.apps.iter())
+            .filter_map(|a| a.bundle_id.as_deref())
+            .collect::<Vec<_>>();
+
+        let mut stale = self
+            .app_ids(team_id)
+            .into_iter()
+            .filter(|a| a.counts_towards_quota(now))
+            .filter(|a| !in_use.contains(&a.identifier))
+            .filter(|a| !refreshed.iter().any(|id| a.identifier.starts_with(id)))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        stale.sort_by_key(|a| a.created);
+        stale
+    }
+
+    pub async fn add_app_ids(&mut self, app_ids: Vec<RegisteredAppId>) -> Result<(), Error> {
+        self.insert_app_ids(app_ids);
+        self.save().await
+    }
+
+    pub fn add_app_ids_sync(&mut self, app_ids: Vec<RegisteredAppId>) -> Result<(), Error> {
+        self.insert_app_ids(app_ids);
+        self.save_sync()
+    }
+
+    fn insert_app_ids(&mut self, app_ids: Vec<RegisteredAppId>) {
+        for app_id in app_ids {
+            self.app_ids.retain(|a| a.app_id_id != app_id.app_id_id);
+            self.app_ids.push(app_id);
+        }
+    }
+
+    pub async fn remove_app_id(&mut self, app_id_id: &str) -> Result<(), Error> {
+        self.app_ids.retain(|a| a.app_id_id != app_id_id);
+        self.save().await
+    }
+
+    pub fn remove_app_id_sync(&mut self, app_id_id: &str) -> Result<(), Error> {
+        self.app_ids.retain(|a| a.app_id_id != app_id_id);
+        self.save_sync()
+    }
 }
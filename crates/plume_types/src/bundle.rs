@@ -1,10 +1,12 @@
 use super::PlistInfoTrait;
-use crate::Error;
+use crate::strings::StringsFile;
+use crate::{Error, relative_path};
 use goblin::mach::{
     fat::FAT_MAGIC,
     header::{MH_MAGIC, MH_MAGIC_64},
 };
 use plist::Value;
+use plume_core::MachO;
 use std::{fs, path::PathBuf};
 
 #[derive(Debug, Clone)]
@@ -48,6 +50,34 @@ impl Bundle {
         collect_embeded_bundles_from_dir(&self.bundle_dir)
     }
 
+    /// Executables of this bundle and everything in it that are still FairPlay
+    /// encrypted, relative to this bundle.
+    pub fn encrypted_binaries(&self) -> Result<Vec<String>, Error> {
+        let mut encrypted = Vec::new();
+
+        for bundle in self.collect_bundles_sorted()? {
+            let executable = match bundle.bundle_type {
+                BundleType::Dylib => bundle.bundle_dir.clone(),
+                _ => match bundle.get_executable() {
+                    Some(executable) => bundle.bundle_dir.join(executable),
+                    None => continue,
+                },
+            };
+
+            let Ok(macho) = MachO::new(&executable) else {
+                continue;
+            };
+
+            if macho.is_encrypted()? {
+                encrypted.push(relative_path(&self.bundle_dir, &executable));
+            }
+        }
+
+        encrypted.sort();
+
+        Ok(encrypted)
+    }
+
     pub fn collect_bundles_sorted(&self) -> Result<Vec<Bundle>, Error> {
         let mut bundles = self.collect_nested_bundles()?;
         bundles.push(self.clone());
@@ -56,6 +86,109 @@ impl Bundle {
 
         Ok(bundles)
     }
+
+    /// Collects the PlugIns, Watch apps and App Clips directly inside this bundle.
+    pub fn collect_removable_bundles(&self) -> Result<Vec<RemovableBundle>, Error> {
+        let mut removable = Vec::new();
+
+        for kind in RemovableBundleKind::ALL {
+            for directory in kind.directories() {
+                let dir = self.bundle_dir.join(directory);
+                if !dir.is_dir() {
+                    continue;
+                }
+
+                for entry in fs::read_dir(&dir)? {
+                    let path = entry?.path();
+                    if !path.extension().is_some_and(|ext| ext == kind.extension()) {
+                        continue;
+                    }
+
+                    let Ok(bundle) = Bundle::new(&path) else {
+                        continue;
+                    };
+
+                    let relative_path = path
+                        .strip_prefix(&self.bundle_dir)
+                        .map_err(|_| Error::BundleInfoPlistMissing)?
+                        .to_string_lossy()
+                        .replace('\\', "/");
+
+                    removable.push(RemovableBundle {
+                        kind,
+                        path: relative_path,
+                        name: bundle.get_name(),
+                        identifier: bundle.get_bundle_identifier(),
+                    });
+                }
+            }
+        }
+
+        removable.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(removable)
+    }
+}
+
+/// Kind of nested bundle that can be stripped before registration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemovableBundleKind {
+    PlugIn,
+    Watch,
+    AppClip,
+}
+
+impl RemovableBundleKind {
+    pub const ALL: [RemovableBundleKind; 3] = [
+        RemovableBundleKind::PlugIn,
+        RemovableBundleKind::Watch,
+        RemovableBundleKind::AppClip,
+    ];
+
+    /// Directories (relative to the app) that hold this kind of bundle.
+    pub fn directories(&self) -> &'static [&'static str] {
+        match self {
+            RemovableBundleKind::PlugIn => &["PlugIns", "Extensions"],
+            RemovableBundleKind::Watch => &["Watch"],
+            RemovableBundleKind::AppClip => &["AppClips"],
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RemovableBundleKind::PlugIn => "appex",
+            RemovableBundleKind::Watch | RemovableBundleKind::AppClip => "app",
+        }
+    }
+
+    pub fn from_relative_path(path: &str) -> Option<Self> {
+        let (directory, rest) = path.split_once('/')?;
+        let (_, ext) = rest.rsplit_once('.')?;
+
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.directories().contains(&directory) && kind.extension() == ext)
+    }
+}
+
+impl std::fmt::Display for RemovableBundleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RemovableBundleKind::PlugIn => write!(f, "PlugIn"),
+            RemovableBundleKind::Watch => write!(f, "Watch App"),
+            RemovableBundleKind::AppClip => write!(f, "App Clip"),
+        }
+    }
+}
+
+/// A PlugIn, Watch app or App Clip found inside an app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovableBundle {
+    pub kind: RemovableBundleKind,
+    /// Path relative to the main app, e.g. `PlugIns/Share.appex`.
+    pub path: String,
+    pub name: Option<String>,
+    pub identifier: Option<String>,
 }
 
 impl Bundle {
@@ -69,10 +202,119 @@ impl Bundle {
         Ok(())
     }
 
-    // TODO: we need to support changing lproj infoplist strings so localized names change as well
+    pub fn remove_info_plist_key(&self, key: &str) -> Result<(), Error> {
+        let mut plist = Value::from_file(&self.info_plist_path)?;
+        let did_remove = plist
+            .as_dictionary_mut()
+            .is_some_and(|dict| dict.remove(key).is_some());
+
+        if did_remove {
+            plist.to_file_xml(&self.info_plist_path)?;
+        }
+
+        Ok(())
+    }
+
     pub fn set_name(&self, new_name: &str) -> Result<(), Error> {
         self.set_info_plist_key("CFBundleDisplayName", new_name)?;
-        self.set_info_plist_key("CFBundleName", new_name)
+        self.set_info_plist_key("CFBundleName", new_name)?;
+        self.set_localized_name(new_name)
+    }
+
+    /// Rewrites the name keys in every `*.lproj/InfoPlist.strings` of this bundle.
+    pub fn set_localized_name(&self, new_name: &str) -> Result<(), Error> {
+        for path in self.localized_info_plist_strings()? {
+            let mut strings = match StringsFile::from_path(&path) {
+                Ok(strings) => strings,
+                Err(e) => {
+                    log::warn!(
+                        "Failed to parse {}, leaving it as is: {}",
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+            let mut did_change = false;
+
+            for key in LOCALIZED_NAME_KEYS {
+                did_change |= strings.replace(key, new_name);
+            }
+
+            if did_change {
+                strings.write_to_path(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the localized names found in `*.lproj/InfoPlist.strings` of this
+    /// bundle and the app extensions inside it, outermost bundle first.
+    pub fn localized_names(&self) -> Result<Vec<LocalizedName>, Error> {
+        let mut paths = Vec::new();
+        for bundle in self.collect_bundles_sorted()?.iter().rev() {
+            if bundle.bundle_dir == self.bundle_dir
+                || bundle.bundle_type == BundleType::AppExtension
+            {
+                paths.extend(bundle.localized_info_plist_strings()?);
+            }
+        }
+
+        let mut names = Vec::new();
+
+        for path in paths {
+            let locale = path
+                .parent()
+                .and_then(|p| p.file_stem())
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+
+            let strings = match StringsFile::from_path(&path) {
+                Ok(strings) => strings,
+                Err(e) => {
+                    log::warn!("Failed to parse {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            let display_name = strings.get("CFBundleDisplayName").map(str::to_string);
+            let bundle_name = strings.get("CFBundleName").map(str::to_string);
+
+            if display_name.is_some() || bundle_name.is_some() {
+                names.push(LocalizedName {
+                    locale,
+                    path,
+                    display_name,
+                    bundle_name,
+                });
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn localized_info_plist_strings(&self) -> Result<Vec<PathBuf>, Error> {
+        if self.bundle_type == BundleType::Dylib {
+            return Ok(Vec::new());
+        }
+
+        let mut paths = Vec::new();
+
+        for entry in fs::read_dir(&self.bundle_dir)? {
+            let path = entry?.path();
+            if path.is_dir() && path.extension().is_some_and(|ext| ext == "lproj") {
+                let strings_path = path.join("InfoPlist.strings");
+                if strings_path.is_file() {
+                    paths.push(strings_path);
+                }
+            }
+        }
+
+        paths.sort();
+
+        Ok(paths)
     }
 
     pub fn set_version(&self, new_version: &str) -> Result<(), Error> {
@@ -142,6 +384,17 @@ impl Bundle {
     }
 }
 
+const LOCALIZED_NAME_KEYS: [&str; 2] = ["CFBundleDisplayName", "CFBundleName"];
+
+/// A localized app name from an `.lproj` folder.
+#[derive(Debug, Clone)]
+pub struct LocalizedName {
+    pub locale: String,
+    pub path: PathBuf,
+    pub display_name: Option<String>,
+    pub bundle_name: Option<String>,
+}
+
 macro_rules! get_plist_string {
     ($self:ident, $key:expr) => {{
         let plist = Value::from_file(&$self.info_plist_path).ok()?;
@@ -251,6 +504,10 @@ fn is_macho_dylib(path: &std::path::Path) -> bool {
         return false;
     }
 
+    is_macho_magic(magic)
+}
+
+pub(crate) fn is_macho_magic(magic: [u8; 4]) -> bool {
     let be = u32::from_be_bytes(magic);
     let le = u32::from_le_bytes(magic);
 
@@ -0,0 +1,93 @@
+use plume_core::{MobileProvision, developer::DeveloperSession};
+
+use crate::{Bundle, BundleType, Device, Error, PlistInfoTrait, Signer};
+
+impl Signer {
+    /// Checks every profile from `register_bundle` against the signing
+    /// certificate and the target device before anything gets signed.
+    ///
+    /// On a mismatch the device is registered again and the profiles are
+    /// refetched once, after which the typed error is returned.
+    pub async fn ensure_profiles_compatible(
+        &mut self,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &String,
+        device: Option<&Device>,
+    ) -> Result<(), Error> {
+        let udid = device.map(|d| d.udid.as_str()).filter(|u| !u.is_empty());
+
+        let Err(e) = self.check_profiles(udid) else {
+            return Ok(());
+        };
+
+        log::warn!("{}, refetching provisioning profiles...", e);
+
+        if let Some(device) = device.filter(|d| !d.udid.is_empty()) {
+            session
+                .qh_ensure_device(team_id, &device.name, &device.udid)
+                .await?;
+        }
+
+        self.refetch_profiles(bundle, session, team_id).await?;
+        self.check_profiles(udid)
+    }
+
+    /// Checks the current profiles without touching the team.
+    pub fn check_profiles(&self, udid: Option<&str>) -> Result<(), Error> {
+        let certificate = self.certificate.as_ref().and_then(|c| c.cert.as_ref());
+
+        for provision in &self.provisioning_files {
+            provision.verify_compatibility(certificate, udid)?;
+        }
+
+        Ok(())
+    }
+
+    async fn refetch_profiles(
+        &mut self,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &String,
+    ) -> Result<(), Error> {
+        let mut provisioning_files = Vec::new();
+
+        for sub_bundle in bundle
+            .collect_bundles_sorted()?
+            .into_iter()
+            .filter(|b| matches!(b.bundle_type(), BundleType::App | BundleType::AppExtension))
+            .filter(|b| {
+                !self.options.embedding.single_profile || b.bundle_dir() == bundle.bundle_dir()
+            })
+        {
+            let id = sub_bundle
+                .get_bundle_identifier()
+                .ok_or_else(|| Error::Other("Failed to get bundle identifier.".into()))?;
+
+            let app_id = session
+                .qh_get_app_id(team_id, &id)
+                .await?
+                .ok_or_else(|| Error::Other(format!("App ID for {} not found.", id)))?;
+
+            let profile_data = session
+                .qh_get_profile(team_id, &app_id.app_id_id)
+                .await?
+                .provisioning_profile
+                .encoded_profile;
+
+            tokio::fs::write(
+                sub_bundle.bundle_dir().join("embedded.mobileprovision"),
+                &profile_data,
+            )
+            .await?;
+
+            provisioning_files.push(MobileProvision::load_with_bytes(
+                profile_data.as_ref().to_vec(),
+            )?);
+        }
+
+        self.provisioning_files = provisioning_files;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,603 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::Error;
+use crate::extract::{DataFormat, read_deb_control};
+
+// Satisfied by the bundled ElleKit or meaningless inside an app
+const PROVIDED_PACKAGES: &[&str] = &[
+    "firmware",
+    "mobilesubstrate",
+    "ellekit",
+    "com.ex.substitute",
+    "com.ex.libsubstitute",
+    "org.coolstar.libhooker",
+    "com.saurik.substrate.safemode",
+    // Its entries become the app's `Settings.bundle`
+    "preferenceloader",
+];
+
+/// The metadata of a .deb, from its `control` file or a `Packages` index.
+#[derive(Debug, Clone, Serialize)]
+pub struct DebControl {
+    pub package: String,
+    pub version: String,
+    pub name: Option<String>,
+    pub architecture: Option<String>,
+    /// Every group has to be satisfied by one of its alternatives.
+    pub depends: Vec<Vec<DebDependency>>,
+    pub provides: Vec<String>,
+}
+
+/// One alternative of a `Depends` entry, e.g. `com.opa334.altlist (>= 1.0)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DebDependency {
+    pub package: String,
+    pub version: Option<(DebRelation, String)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DebRelation {
+    Earlier,
+    EarlierOrEqual,
+    Equal,
+    LaterOrEqual,
+    Later,
+}
+
+impl DebControl {
+    pub fn from_deb(path: &Path) -> Result<Self, Error> {
+        let control = read_deb_control(path)?;
+        let stanza = parse_stanzas(&control)
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::TweakControlInvalid(path.display().to_string()))?;
+
+        Self::from_stanza(&stanza)
+            .ok_or_else(|| Error::TweakControlInvalid(path.display().to_string()))
+    }
+
+    fn from_stanza(stanza: &HashMap<String, String>) -> Option<Self> {
+        let list = |key: &str| {
+            stanza
+                .get(key)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|v| !v.is_empty())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+
+        let depends = list("depends")
+            .into_iter()
+            .chain(list("pre-depends"))
+            .map(|group| {
+                group
+                    .split('|')
+                    .filter_map(DebDependency::parse)
+                    .collect::<Vec<_>>()
+            })
+            .filter(|group| !group.is_empty())
+            .collect();
+
+        let provides = list("provides")
+            .into_iter()
+            .filter_map(DebDependency::parse)
+            .map(|d| d.package)
+            .collect();
+
+        Some(Self {
+            package: stanza.get("package")?.clone(),
+            version: stanza.get("version")?.clone(),
+            name: stanza.get("name").cloned(),
+            architecture: stanza.get("architecture").cloned(),
+            depends,
+            provides,
+        })
+    }
+
+    fn satisfies(&self, dependency: &DebDependency) -> bool {
+        if self.package == dependency.package {
+            return dependency.matches_version(&self.version);
+        }
+
+        // Provided names carry no version of their own
+        dependency.version.is_none() && self.provides.contains(&dependency.package)
+    }
+}
+
+impl DebDependency {
+    fn parse(value: &str) -> Option<Self> {
+        // Drop `[arch]` and `<profile>` restrictions, `<<` inside the version stays
+        let end = value
+            .char_indices()
+            .scan(0, |depth, (i, c)| {
+                match c {
+                    '(' => *depth += 1,
+                    ')' => *depth -= 1,
+                    _ => {}
+                }
+                Some((i, c, *depth))
+            })
+            .find(|(_, c, depth)| *depth == 0 && matches!(c, '[' | '<'))
+            .map_or(value.len(), |(i, _, _)| i);
+        let value = value[..end].trim();
+        let (name, version) = match value.split_once('(') {
+            Some((name, version)) => (name.trim(), Some(version.trim_end_matches(')').trim())),
+            None => (value, None),
+        };
+        // `name:any` multiarch qualifiers
+        let package = name.split(':').next()?.trim().to_string();
+        if package.is_empty() {
+            return None;
+        }
+
+        let version = version.and_then(|version| {
+            let split = version
+                .find(|c: char| !"<>=".contains(c))
+                .unwrap_or(version.len());
+            let (relation, version) = version.split_at(split);
+            let relation = match relation {
+                "<<" => DebRelation::Earlier,
+                // `<` and `>` are obsolete spellings of `<=` and `>=`
+                "<=" | "<" => DebRelation::EarlierOrEqual,
+                "=" => DebRelation::Equal,
+                ">=" | ">" => DebRelation::LaterOrEqual,
+                ">>" => DebRelation::Later,
+                _ => return None,
+            };
+            Some((relation, version.trim().to_string()))
+        });
+
+        Some(Self { package, version })
+    }
+
+    fn matches_version(&self, version: &str) -> bool {
+        let Some((relation, required)) = &self.version else {
+            return true;
+        };
+
+        let ordering = compare_versions(version, required);
+        match relation {
+            DebRelation::Earlier => ordering == Ordering::Less,
+            DebRelation::EarlierOrEqual => ordering != Ordering::Greater,
+            DebRelation::Equal => ordering == Ordering::Equal,
+            DebRelation::LaterOrEqual => ordering != Ordering::Less,
+            DebRelation::Later => ordering == Ordering::Greater,
+        }
+    }
+}
+
+impl std::fmt::Display for DebDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.package)?;
+        if let Some((relation, version)) = &self.version {
+            let relation = match relation {
+                DebRelation::Earlier => "<<",
+                DebRelation::EarlierOrEqual => "<=",
+                DebRelation::Equal => "=",
+                DebRelation::LaterOrEqual => ">=",
+                DebRelation::Later => ">>",
+            };
+            write!(f, " ({} {})", relation, version)?;
+        }
+        Ok(())
+    }
+}
+
+/// The .debs to install before a tweak, dependencies first.
+#[derive(Debug, Clone, Default)]
+pub struct DebResolution {
+    pub install: Vec<PathBuf>,
+    /// `Depends` entries nothing in the repository satisfies.
+    pub missing: Vec<String>,
+}
+
+/// A local folder of .debs or an APT `Packages` index to resolve dependencies from.
+#[derive(Debug, Clone, Default)]
+pub struct DebRepository {
+    packages: Vec<(DebControl, PathBuf)>,
+}
+
+impl DebRepository {
+    /// Opens a directory of .debs, or a `Packages` index whose `Filename`s
+    /// are relative to the index.
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        if path.is_dir() {
+            Self::from_dir(path)
+        } else {
+            Self::from_index(path)
+        }
+    }
+
+    fn from_dir(dir: &Path) -> Result<Self, Error> {
+        let mut packages = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+
+        while let Some(dir) = pending.pop() {
+            for entry in std::fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if path.extension().is_some_and(|e| e == "deb") {
+                    match DebControl::from_deb(&path) {
+                        Ok(control) => packages.push((control, path)),
+                        Err(e) => log::warn!("Ignoring {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+
+        Ok(Self { packages })
+    }
+
+    fn from_index(index: &Path) -> Result<Self, Error> {
+        let data = std::fs::read(index)?;
+        let root = index.parent().unwrap_or(Path::new(""));
+
+        // `Packages.gz`, `Packages.xz` and friends
+        let mut text = String::new();
+        match DataFormat::detect(&data).filter(|f| *f != DataFormat::Tar) {
+            Some(format) => {
+                format.decoder(data.as_slice())?.read_to_string(&mut text)?;
+            }
+            None => text = String::from_utf8_lossy(&data).into_owned(),
+        }
+
+        let packages = parse_stanzas(&text)
+            .into_iter()
+            .filter_map(|stanza| {
+                let filename = stanza.get("filename")?;
+                let mut path = root.join(filename);
+                // Flat folders next to the index instead of an APT pool
+                if !path.exists() {
+                    path = root.join(Path::new(filename).file_name()?);
+                }
+                Some((DebControl::from_stanza(&stanza)?, path))
+            })
+            .collect();
+
+        Ok(Self { packages })
+    }
+
+    /// Resolves the dependency closure of `control`, in installation order.
+    pub fn resolve(&self, control: &DebControl) -> DebResolution {
+        let mut resolution = DebResolution::default();
+        let mut picked = vec![control];
+
+        self.resolve_into(control, control, &mut picked, &mut resolution);
+
+        resolution
+    }
+
+    fn resolve_into<'a>(
+        &'a self,
+        root: &DebControl,
+        control: &DebControl,
+        picked: &mut Vec<&'a DebControl>,
+        resolution: &mut DebResolution,
+    ) {
+        for group in &control.depends {
+            // Met by something already picked, at the version this group asks for
+            let already_met = group.iter().any(|d| {
+                PROVIDED_PACKAGES.contains(&d.package.as_str())
+                    || d.package.starts_with("cy+")
+                    || picked.iter().any(|p| p.satisfies(d))
+            });
+            if already_met {
+                continue;
+            }
+
+            // A second version of a picked package can't be installed next to it
+            let candidate = group
+                .iter()
+                .filter_map(|d| self.best_candidate(root, d))
+                .find(|(candidate, _)| !picked.iter().any(|p| p.package == candidate.package));
+
+            let Some((candidate, path)) = candidate else {
+                let group = group
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                log::warn!(
+                    "{} depends on {}, which isn't available",
+                    control.package,
+                    group
+                );
+                resolution.missing.push(group);
+                continue;
+            };
+
+            picked.push(candidate);
+
+            self.resolve_into(root, candidate, picked, resolution);
+            resolution.install.push(path.clone());
+        }
+    }
+
+    // The newest matching package built for the same jailbreak as the tweak
+    fn best_candidate(
+        &self,
+        root: &DebControl,
+        dependency: &DebDependency,
+    ) -> Option<&(DebControl, PathBuf)> {
+        self.packages
+            .iter()
+            .filter(|(control, path)| control.satisfies(dependency) && path.exists())
+            .filter(
+                |(control, _)| match (&root.architecture, &control.architecture) {
+                    (Some(root), Some(arch)) => arch == root || arch == "all",
+                    _ => true,
+                },
+            )
+            .max_by(|(a, _), (b, _)| compare_versions(&a.version, &b.version))
+    }
+}
+
+// RFC 822 style stanzas separated by blank lines, keys lowercased
+fn parse_stanzas(text: &str) -> Vec<HashMap<String, String>> {
+    let mut stanzas = Vec::new();
+    let mut current = HashMap::new();
+    let mut last_key: Option<String> = None;
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                stanzas.push(std::mem::take(&mut current));
+            }
+            last_key = None;
+        } else if line.starts_with([' ', '\t']) {
+            if let Some(value) = last_key.as_ref().and_then(|k| current.get_mut(k)) {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().to_lowercase();
+            current.insert(key.clone(), value.trim().to_string());
+            last_key = Some(key);
+        }
+    }
+
+    if !current.is_empty() {
+        stanzas.push(current);
+    }
+
+    stanzas
+}
+
+/// Compares two Debian versions the way dpkg does.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (epoch_a, upstream_a, revision_a) = split_version(a);
+    let (epoch_b, upstream_b, revision_b) = split_version(b);
+
+    epoch_a
+        .cmp(&epoch_b)
+        .then_with(|| compare_fragment(upstream_a, upstream_b))
+        .then_with(|| compare_fragment(revision_a, revision_b))
+}
+
+fn split_version(version: &str) -> (u64, &str, &str) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+        None => (0, version),
+    };
+
+    match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (epoch, upstream, revision),
+        None => (epoch, rest, ""),
+    }
+}
+
+// `~` sorts before everything, even the end, and letters before other symbols
+fn char_order(c: Option<char>) -> i32 {
+    match c {
+        Some('~') => -1,
+        None => 0,
+        Some(c) if c.is_ascii_digit() => 0,
+        Some(c) if c.is_ascii_alphabetic() => c as i32,
+        Some(c) => c as i32 + 256,
+    }
+}
+
+fn compare_fragment(mut a: &str, mut b: &str) -> Ordering {
+    while !a.is_empty() || !b.is_empty() {
+        loop {
+            let char_a = a.chars().next().filter(|c| !c.is_ascii_digit());
+            let char_b = b.chars().next().filter(|c| !c.is_ascii_digit());
+            if char_a.is_none() && char_b.is_none() {
+                break;
+            }
+
+            let ordering = char_order(char_a).cmp(&char_order(char_b));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+
+            a = &a[char_a.map_or(0, char::len_utf8)..];
+            b = &b[char_b.map_or(0, char::len_utf8)..];
+        }
+
+        let digits_a = a.len() - a.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let digits_b = b.len() - b.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let number_a = a[..digits_a].trim_start_matches('0');
+        let number_b = b[..digits_b].trim_start_matches('0');
+
+        let ordering = number_a
+            .len()
+            .cmp(&number_b.len())
+            .then_with(|| number_a.cmp(number_b));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+
+        a = &a[digits_a..];
+        b = &b[digits_b..];
+    }
+
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn control(text: &str) -> DebControl {
+        DebControl::from_stanza(&parse_stanzas(text)[0]).unwrap()
+    }
+
+    // Candidates are only picked when their .deb is on disk
+    fn repository(stanzas: &[&str]) -> DebRepository {
+        let dir = std::env::temp_dir().join(format!("plume-control-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let packages = stanzas
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let path = dir.join(format!("{}.deb", i));
+                std::fs::write(&path, []).unwrap();
+                (control(text), path)
+            })
+            .collect();
+
+        DebRepository { packages }
+    }
+
+    fn installed(repository: &DebRepository, resolution: &DebResolution) -> Vec<String> {
+        resolution
+            .install
+            .iter()
+            .map(|path| {
+                let (control, _) = repository.packages.iter().find(|(_, p)| p == path).unwrap();
+                format!("{} {}", control.package, control.version)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tilde_sorts_before_release() {
+        assert_eq!(compare_versions("1.0~rc1", "1.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.0~rc1", "1.0~rc2"), Ordering::Less);
+        assert_eq!(compare_versions("1.0~~", "1.0~"), Ordering::Less);
+    }
+
+    #[test]
+    fn epoch_outranks_upstream() {
+        assert_eq!(compare_versions("1:0.1", "2.0"), Ordering::Greater);
+        assert_eq!(compare_versions("0:2.0", "2.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn revision_breaks_ties() {
+        assert_eq!(compare_versions("1.0-2", "1.0-1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0-1-1", "1.0-1"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0-0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn digits_compare_numerically() {
+        assert_eq!(compare_versions("1.10", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("1.01", "1.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0a", "1.0+"), Ordering::Less);
+        assert_eq!(compare_versions("1.0", "1.0a"), Ordering::Less);
+    }
+
+    #[test]
+    fn parses_relations_and_restrictions() {
+        let tweak = control(
+            "Package: tweak\nVersion: 1.0\nDepends: a (<< 2.0) [iphoneos-arm64] <!nocheck>, b:any (>= 1.0)\n",
+        );
+
+        assert_eq!(
+            tweak.depends,
+            [
+                vec![DebDependency {
+                    package: "a".into(),
+                    version: Some((DebRelation::Earlier, "2.0".into())),
+                }],
+                vec![DebDependency {
+                    package: "b".into(),
+                    version: Some((DebRelation::LaterOrEqual, "1.0".into())),
+                }],
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_first_available_alternative() {
+        let repository = repository(&["Package: b\nVersion: 1.0\n"]);
+        let tweak = control("Package: tweak\nVersion: 1.0\nDepends: a | b\n");
+
+        let resolution = repository.resolve(&tweak);
+
+        assert_eq!(installed(&repository, &resolution), ["b 1.0"]);
+        assert!(resolution.missing.is_empty());
+    }
+
+    #[test]
+    fn unpicked_alternative_stays_unmet() {
+        let repository = repository(&["Package: b\nVersion: 1.0\n"]);
+        let tweak = control("Package: tweak\nVersion: 1.0\nDepends: a | b, a\n");
+
+        let resolution = repository.resolve(&tweak);
+
+        assert_eq!(installed(&repository, &resolution), ["b 1.0"]);
+        assert_eq!(resolution.missing, ["a"]);
+    }
+
+    #[test]
+    fn resolves_provided_names() {
+        let repository = repository(&[
+            "Package: libfoo\nVersion: 2.0\nProvides: foo\n",
+            "Package: tool\nVersion: 1.0\nDepends: foo\n",
+        ]);
+        let tweak = control("Package: tweak\nVersion: 1.0\nDepends: foo, tool\n");
+
+        let resolution = repository.resolve(&tweak);
+
+        assert_eq!(
+            installed(&repository, &resolution),
+            ["libfoo 2.0", "tool 1.0"]
+        );
+        assert!(resolution.missing.is_empty());
+    }
+
+    #[test]
+    fn picks_newest_matching_version() {
+        let repository = repository(&[
+            "Package: lib\nVersion: 1.0\n",
+            "Package: lib\nVersion: 2.0\n",
+            "Package: lib\nVersion: 3.0\n",
+        ]);
+        let tweak = control("Package: tweak\nVersion: 1.0\nDepends: lib (<< 3.0)\n");
+
+        let resolution = repository.resolve(&tweak);
+
+        assert_eq!(installed(&repository, &resolution), ["lib 2.0"]);
+    }
+
+    #[test]
+    fn later_stricter_version_is_checked() {
+        let repository = repository(&[
+            "Package: lib\nVersion: 1.0\n",
+            "Package: lib\nVersion: 2.0\n",
+            "Package: tool\nVersion: 1.0\nDepends: lib (>= 2.0)\n",
+        ]);
+        let tweak = control("Package: tweak\nVersion: 1.0\nDepends: lib (<< 2.0), tool\n");
+
+        let resolution = repository.resolve(&tweak);
+
+        assert_eq!(installed(&repository, &resolution), ["lib 1.0", "tool 1.0"]);
+        assert_eq!(resolution.missing, ["lib (>= 2.0)"]);
+    }
+}
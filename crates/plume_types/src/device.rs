@@ -65,6 +65,24 @@ impl Device {
         Ok(get_dict_string!(values, "DeviceName"))
     }
 
+    /// The CPU architecture lockdown reports, e.g. `arm64e`.
+    pub async fn cpu_architecture(&self) -> Result<String, Error> {
+        let device = match &self.usbmuxd_device {
+            Some(dev) => dev,
+            None => return Err(Error::Other("Device is not connected via USB".to_string())),
+        };
+
+        let mut lockdown =
+            LockdownClient::connect(&device.to_provider(UsbmuxdAddr::default(), CONNECTION_LABEL))
+                .await?;
+        let value = lockdown.get_value(Some("CPUArchitecture"), None).await?;
+
+        value
+            .as_string()
+            .map(String::from)
+            .ok_or_else(|| Error::Other("Device did not report its CPU architecture".to_string()))
+    }
+
     pub async fn installed_apps(&self) -> Result<Vec<SignerAppReal>, Error> {
         let device = match &self.usbmuxd_device {
             Some(dev) => dev,
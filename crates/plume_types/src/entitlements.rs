@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use plist::Value;
+use plume_core::TEAM_ID_REGEX;
+use plume_core::developer::{DeveloperSession, qh::teams::TeamMembership};
+use serde::Serialize;
+
+use crate::{Bundle, BundleType, Error, PlistInfoTrait, Signer};
+
+// Keys every development profile carries, no capability required
+const PROFILE_PROVIDED_ENTITLEMENTS: &[&str] = &[
+    "application-identifier",
+    "com.apple.developer.team-identifier",
+    "get-task-allow",
+    "keychain-access-groups",
+    "com.apple.security.application-groups",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntitlementStatus {
+    /// Granted by the profile, either by default or through a capability.
+    Supported,
+    /// Backed by a capability this team isn't allowed to enable.
+    Blocked,
+    /// No capability provides it, so it won't survive `merge_entitlements`.
+    Dropped,
+}
+
+impl std::fmt::Display for EntitlementStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EntitlementStatus::Supported => write!(f, "supported"),
+            EntitlementStatus::Blocked => write!(f, "blocked"),
+            EntitlementStatus::Dropped => write!(f, "dropped"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntitlementEntry {
+    pub key: String,
+    pub status: EntitlementStatus,
+    /// Capability that provides this entitlement, if any.
+    pub capability: Option<String>,
+    /// Values that will be stripped even though the key itself is kept.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_values: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleEntitlements {
+    pub name: String,
+    pub identifier: Option<String>,
+    pub entitlements: Vec<EntitlementEntry>,
+}
+
+impl BundleEntitlements {
+    pub fn with_status(
+        &self,
+        status: EntitlementStatus,
+    ) -> impl Iterator<Item = &EntitlementEntry> {
+        self.entitlements.iter().filter(move |e| e.status == status)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntitlementReport {
+    pub membership: TeamMembership,
+    pub bundles: Vec<BundleEntitlements>,
+}
+
+impl EntitlementReport {
+    /// Whether any bundle asks for something it won't get.
+    pub fn has_issues(&self) -> bool {
+        self.bundles.iter().any(|b| {
+            b.entitlements
+                .iter()
+                .any(|e| e.status != EntitlementStatus::Supported || !e.dropped_values.is_empty())
+        })
+    }
+}
+
+impl std::fmt::Display for EntitlementReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Team membership: {}", self.membership)?;
+
+        for bundle in &self.bundles {
+            writeln!(
+                f,
+                "{} ({})",
+                bundle.name,
+                bundle.identifier.as_deref().unwrap_or("???")
+            )?;
+
+            if bundle.entitlements.is_empty() {
+                writeln!(f, "  no entitlements")?;
+            }
+
+            for entry in &bundle.entitlements {
+                write!(f, "  [{}] {}", entry.status, entry.key)?;
+                if let Some(capability) = &entry.capability {
+                    write!(f, " ({})", capability)?;
+                }
+                writeln!(f)?;
+                for value in &entry.dropped_values {
+                    writeln!(f, "    [dropped] {}", value)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Signer {
+    /// Classifies the entitlements of every bundle `register_bundle` would
+    /// register, before anything is created on the team.
+    pub async fn entitlement_report(
+        &self,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &String,
+    ) -> Result<EntitlementReport, Error> {
+        let membership = session.qh_team_membership(team_id).await?;
+        let unallowed = membership.unallowed_capabilities();
+
+        // profile key -> capability id
+        let mut capabilities = HashMap::new();
+        for capability in session.v1_list_capabilities(team_id).await?.data {
+            for entitlement in capability.attributes.entitlements.iter().flatten() {
+                capabilities
+                    .entry(entitlement.profile_key.clone())
+                    .or_insert_with(|| capability.id.clone());
+            }
+        }
+
+        let team_prefix = regex::Regex::new(TEAM_ID_REGEX).unwrap();
+        let mut bundles = Vec::new();
+
+        for sub_bundle in bundle
+            .collect_bundles_sorted()?
+            .into_iter()
+            .filter(|b| matches!(b.bundle_type(), BundleType::App | BundleType::AppExtension))
+            .filter(|b| {
+                !self.options.embedding.single_profile || b.bundle_dir() == bundle.bundle_dir()
+            })
+        {
+            let executable = sub_bundle
+                .get_executable()
+                .ok_or_else(|| Error::Other("Failed to get bundle executable name.".into()))?;
+            let macho = plume_core::MachO::new(&sub_bundle.bundle_dir().join(&executable))?;
+
+            let mut entitlements = Vec::new();
+
+            for (key, value) in macho.entitlements().iter().flatten() {
+                let capability = capabilities.get(key).cloned();
+
+                let status = if PROFILE_PROVIDED_ENTITLEMENTS.contains(&key.as_str()) {
+                    EntitlementStatus::Supported
+                } else {
+                    match &capability {
+                        Some(id) if unallowed.contains(&id.as_str()) => EntitlementStatus::Blocked,
+                        Some(_) => EntitlementStatus::Supported,
+                        None => EntitlementStatus::Dropped,
+                    }
+                };
+
+                // merge_entitlements only keeps team prefixed keychain groups
+                let dropped_values = match (key.as_str(), value) {
+                    ("keychain-access-groups", Value::Array(groups)) => groups
+                        .iter()
+                        .filter_map(Value::as_string)
+                        .filter(|g| !team_prefix.is_match(g))
+                        .map(String::from)
+                        .collect(),
+                    _ => Vec::new(),
+                };
+
+                entitlements.push(EntitlementEntry {
+                    key: key.clone(),
+                    status,
+                    capability,
+                    dropped_values,
+                });
+            }
+
+            bundles.push(BundleEntitlements {
+                name: sub_bundle.get_name().unwrap_or_else(|| executable.clone()),
+                identifier: sub_bundle.get_bundle_identifier(),
+                entitlements,
+            });
+        }
+
+        Ok(EntitlementReport {
+            membership,
+            bundles,
+        })
+    }
+}
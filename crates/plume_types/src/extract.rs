@@ -0,0 +1,630 @@
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use zip::ZipArchive;
+
+use crate::Error;
+
+// Longest symlink target we'll read out of an archive
+const MAX_LINK_TARGET: u64 = 4096;
+
+/// Limits enforced while extracting IPAs and tweaks from untrusted sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractLimits {
+    /// Maximum number of entries, including directories and links.
+    pub max_entries: usize,
+    /// Maximum number of bytes written for all entries together.
+    pub max_total_size: u64,
+    /// Maximum number of bytes written for a single entry.
+    pub max_entry_size: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        ExtractLimits {
+            max_entries: 200_000,
+            max_total_size: 16 * 1024 * 1024 * 1024,
+            max_entry_size: 4 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+/// Writes archive entries below a root directory, refusing anything that
+/// would end up outside of it.
+struct SafeExtractor {
+    root: PathBuf,
+    limits: ExtractLimits,
+    entries: usize,
+    total_size: u64,
+}
+
+impl SafeExtractor {
+    fn new(root: &Path, limits: ExtractLimits) -> Result<Self, Error> {
+        fs::create_dir_all(root)?;
+
+        Ok(Self {
+            root: root.canonicalize()?,
+            limits,
+            entries: 0,
+            total_size: 0,
+        })
+    }
+
+    fn count_entry(&mut self) -> Result<(), Error> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(Error::ArchiveTooManyEntries(self.limits.max_entries));
+        }
+        Ok(())
+    }
+
+    // Relative path for an entry name, `None` for the root itself
+    fn entry_path(name: &Path) -> Result<Option<PathBuf>, Error> {
+        let mut relative = PathBuf::new();
+
+        for component in name.components() {
+            match component {
+                Component::Normal(part) => relative.push(part),
+                // Leading `/` and `./` are common in tarballs, treat them as the root
+                Component::RootDir | Component::CurDir => {}
+                Component::ParentDir | Component::Prefix(_) => {
+                    return Err(Error::ArchivePathTraversal(name.display().to_string()));
+                }
+            }
+        }
+
+        Ok((!relative.as_os_str().is_empty()).then_some(relative))
+    }
+
+    // Creates every directory of `relative`, checking links left by earlier entries
+    fn create_dirs(&self, relative: &Path) -> Result<PathBuf, Error> {
+        let mut path = self.root.clone();
+
+        for component in relative.components() {
+            path.push(component);
+
+            match fs::symlink_metadata(&path) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    if !path.canonicalize()?.starts_with(&self.root) {
+                        return Err(Error::ArchivePathTraversal(relative.display().to_string()));
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => fs::create_dir(&path)?,
+            }
+        }
+
+        Ok(path)
+    }
+
+    // Path to write a non-directory entry to, never through an existing link
+    fn prepare_entry(&self, relative: &Path) -> Result<PathBuf, Error> {
+        let parent = self.create_dirs(relative.parent().unwrap_or(Path::new("")))?;
+        let path = parent.join(relative.file_name().unwrap_or_default());
+
+        if let Ok(metadata) = fs::symlink_metadata(&path) {
+            if metadata.file_type().is_symlink() || metadata.is_file() {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(path)
+    }
+
+    fn add_dir(&mut self, relative: &Path, mode: Option<u32>) -> Result<(), Error> {
+        self.count_entry()?;
+        let path = self.create_dirs(relative)?;
+        set_mode(&path, mode, 0o700)
+    }
+
+    fn add_file<R: Read>(
+        &mut self,
+        relative: &Path,
+        reader: R,
+        mode: Option<u32>,
+    ) -> Result<(), Error> {
+        self.count_entry()?;
+        let path = self.prepare_entry(relative)?;
+
+        let remaining = self.limits.max_total_size.saturating_sub(self.total_size);
+        let limit = self.limits.max_entry_size.min(remaining);
+
+        let mut file = fs::File::create(&path)?;
+        let written = io::copy(&mut reader.take(limit.saturating_add(1)), &mut file)?;
+        drop(file);
+
+        if written > limit {
+            fs::remove_file(&path).ok();
+            return Err(if written > self.limits.max_entry_size {
+                Error::ArchiveEntryTooLarge {
+                    path: relative.display().to_string(),
+                    limit: self.limits.max_entry_size,
+                }
+            } else {
+                Error::ArchiveTooLarge(self.limits.max_total_size)
+            });
+        }
+
+        self.total_size += written;
+        set_mode(&path, mode, 0o600)
+    }
+
+    fn add_symlink(&mut self, relative: &Path, target: &Path) -> Result<(), Error> {
+        self.count_entry()?;
+
+        let escapes = || Error::ArchiveLinkEscapes {
+            path: relative.display().to_string(),
+            target: target.display().to_string(),
+        };
+
+        // From where the link really ends up, earlier links can move its parent
+        let path = self.prepare_entry(relative)?;
+        let parent = path.parent().unwrap_or(&self.root).canonicalize()?;
+        match resolve_link_target(&parent, target) {
+            Some(resolved) if resolved.starts_with(&self.root) => {}
+            _ => return Err(escapes()),
+        }
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, &path)?;
+
+        // Without symlink support keep the target as file contents, like zip does
+        #[cfg(not(unix))]
+        fs::write(&path, target.to_string_lossy().replace('\\', "/"))?;
+
+        Ok(())
+    }
+
+    fn add_hard_link(&mut self, relative: &Path, target: &Path) -> Result<(), Error> {
+        let source = Self::entry_path(target)?
+            .map(|t| self.root.join(t))
+            .ok_or_else(|| Error::ArchivePathTraversal(target.display().to_string()))?;
+
+        if !source.canonicalize()?.starts_with(&self.root) {
+            return Err(Error::ArchiveLinkEscapes {
+                path: relative.display().to_string(),
+                target: target.display().to_string(),
+            });
+        }
+
+        // Copied so the limits apply to the linked data as well
+        let mode = source.metadata()?.permissions();
+        self.add_file(relative, fs::File::open(&source)?, None)?;
+        fs::set_permissions(self.root.join(relative), mode)?;
+
+        Ok(())
+    }
+}
+
+/// Extracts every entry of a zip archive below `root`.
+pub(crate) fn extract_zip<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    root: &Path,
+    limits: ExtractLimits,
+) -> Result<(), Error> {
+    if archive.len() > limits.max_entries {
+        return Err(Error::ArchiveTooManyEntries(limits.max_entries));
+    }
+
+    let mut extractor = SafeExtractor::new(root, limits)?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(relative) = SafeExtractor::entry_path(Path::new(entry.name()))? else {
+            continue;
+        };
+        let mode = entry.unix_mode();
+
+        if entry.is_dir() {
+            extractor.add_dir(&relative, mode)?;
+        } else if entry.is_symlink() {
+            let mut target = String::new();
+            (&mut entry)
+                .take(MAX_LINK_TARGET)
+                .read_to_string(&mut target)?;
+            extractor.add_symlink(&relative, Path::new(&target))?;
+        } else {
+            extractor.add_file(&relative, &mut entry, mode)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts every entry of a tar stream below `root`.
+pub(crate) fn extract_tar<R: Read>(
+    reader: R,
+    root: &Path,
+    limits: ExtractLimits,
+) -> Result<(), Error> {
+    let mut archive = tar::Archive::new(reader);
+    let mut extractor = SafeExtractor::new(root, limits)?;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        let Some(relative) = SafeExtractor::entry_path(&name)? else {
+            continue;
+        };
+        let entry_type = entry.header().entry_type();
+        let mode = entry.header().mode().ok();
+
+        if entry_type.is_dir() {
+            extractor.add_dir(&relative, mode)?;
+        } else if entry_type.is_symlink() || entry_type.is_hard_link() {
+            let target = entry
+                .link_name()?
+                .ok_or_else(|| Error::ArchivePathTraversal(name.display().to_string()))?
+                .into_owned();
+
+            if entry_type.is_symlink() {
+                extractor.add_symlink(&relative, &target)?;
+            } else {
+                extractor.add_hard_link(&relative, &target)?;
+            }
+        } else if entry_type.is_file() || entry_type.is_contiguous() {
+            extractor.add_file(&relative, &mut entry, mode)?;
+        } else {
+            log::debug!("Skipping special archive entry: {}", name.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts the `data.tar` member of a `.deb` below `root`.
+pub(crate) fn extract_deb(deb: &Path, root: &Path, limits: ExtractLimits) -> Result<(), Error> {
+    let mut archive = ar::Archive::new(fs::File::open(deb)?);
+
+    match deb_member(&mut archive, "data.tar")? {
+        Some(reader) => extract_tar(reader, root, limits),
+        None => Err(Error::TweakDataMissing),
+    }
+}
+
+// Longest control file we'll read, real ones are a few hundred bytes
+const MAX_CONTROL_SIZE: u64 = 1024 * 1024;
+
+/// Reads the `control` file out of a .deb's `control.tar`.
+pub(crate) fn read_deb_control(deb: &Path) -> Result<String, Error> {
+    let mut archive = ar::Archive::new(fs::File::open(deb)?);
+
+    let Some(reader) = deb_member(&mut archive, "control.tar")? else {
+        return Err(Error::TweakControlMissing);
+    };
+
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries()? {
+        let entry = entry?;
+        let is_control =
+            SafeExtractor::entry_path(&entry.path()?)?.is_some_and(|p| p == Path::new("control"));
+
+        if is_control {
+            let mut control = String::new();
+            entry.take(MAX_CONTROL_SIZE).read_to_string(&mut control)?;
+            return Ok(control);
+        }
+    }
+
+    Err(Error::TweakControlMissing)
+}
+
+// The decompressed contents of the first `name.*` member
+fn deb_member<'a>(
+    archive: &'a mut ar::Archive<fs::File>,
+    name: &str,
+) -> Result<Option<Box<dyn Read + 'a>>, Error> {
+    while let Some(entry) = archive.next_entry() {
+        let mut entry = entry.map_err(|e| {
+            Error::TweakExtractionFailed(format!("Failed to read .ar archive: {}", e))
+        })?;
+        // GNU ar terminates member names with a slash
+        let identifier = String::from_utf8_lossy(entry.header().identifier())
+            .trim_end_matches('/')
+            .to_string();
+
+        if !identifier.starts_with(name) {
+            continue;
+        }
+
+        // Enough for the ustar magic, the extension isn't trusted
+        let mut head = Vec::with_capacity(512);
+        (&mut entry).take(512).read_to_end(&mut head)?;
+
+        let format = DataFormat::detect(&head)
+            .ok_or_else(|| Error::TweakUnsupportedData(identifier.clone()))?;
+        log::debug!("Reading {} as {:?}", identifier, format);
+
+        return format.decoder(io::Cursor::new(head).chain(entry)).map(Some);
+    }
+
+    Ok(None)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataFormat {
+    Tar,
+    Gzip,
+    Xz,
+    Lzma,
+    Bzip2,
+    Zstd,
+}
+
+impl DataFormat {
+    pub(crate) fn detect(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&[0x1f, 0x8b]) {
+            Some(DataFormat::Gzip)
+        } else if head.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(DataFormat::Xz)
+        } else if head.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(DataFormat::Zstd)
+        } else if head.starts_with(b"BZh") {
+            Some(DataFormat::Bzip2)
+        } else if head.get(257..262) == Some(b"ustar") {
+            Some(DataFormat::Tar)
+        } else if head.starts_with(&[0x5d, 0x00, 0x00]) {
+            // Legacy lzma has no magic, this is the properties byte xz-utils writes
+            Some(DataFormat::Lzma)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn decoder<'a, R: Read + 'a>(self, reader: R) -> Result<Box<dyn Read + 'a>, Error> {
+        use bzip2::read::BzDecoder;
+        use flate2::read::GzDecoder;
+        use xz2::{read::XzDecoder, stream::Stream};
+
+        Ok(match self {
+            DataFormat::Tar => Box::new(reader),
+            DataFormat::Gzip => Box::new(GzDecoder::new(reader)),
+            DataFormat::Xz => Box::new(XzDecoder::new(reader)),
+            DataFormat::Bzip2 => Box::new(BzDecoder::new(reader)),
+            DataFormat::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+            DataFormat::Lzma => {
+                let stream = Stream::new_lzma_decoder(u64::MAX).map_err(|e| {
+                    Error::TweakExtractionFailed(format!("Failed to extract data.tar: {}", e))
+                })?;
+                Box::new(XzDecoder::new_stream(reader, stream))
+            }
+        })
+    }
+}
+
+// Where `target` points from the directory `dir`, following the links that exist so
+// far and resolving the rest lexically. `None` for absolute targets.
+fn resolve_link_target(dir: &Path, target: &Path) -> Option<PathBuf> {
+    let mut resolved = dir.to_path_buf();
+
+    for component in target.components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                if let Ok(real) = resolved.canonicalize() {
+                    resolved = real;
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !resolved.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    Some(resolved)
+}
+
+fn set_mode(path: &Path, mode: Option<u32>, owner: u32) -> Result<(), Error> {
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        // Keep owner access, the signer rewrites these files later
+        fs::set_permissions(path, fs::Permissions::from_mode((mode & 0o777) | owner))?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = (path, mode, owner);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use uuid::Uuid;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    // Scratch directory, archives extract into `root` so escapes land next to it
+    fn scratch() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("plume-extract-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn zip_with(
+        build: impl FnOnce(&mut ZipWriter<io::Cursor<Vec<u8>>>),
+    ) -> ZipArchive<io::Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
+        build(&mut zip);
+        ZipArchive::new(zip.finish().unwrap()).unwrap()
+    }
+
+    // Raw headers, `tar` refuses to write `..` itself
+    fn tar_entry(
+        builder: &mut tar::Builder<Vec<u8>>,
+        kind: tar::EntryType,
+        path: &str,
+        link: &str,
+    ) {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+        header.as_old_mut().linkname[..link.len()].copy_from_slice(link.as_bytes());
+        header.set_entry_type(kind);
+        header.set_mode(0o644);
+        header.set_size(0);
+        header.set_cksum();
+        builder.append(&header, io::empty()).unwrap();
+    }
+
+    fn extract_tar_entries(
+        dir: &Path,
+        entries: &[(tar::EntryType, &str, &str)],
+    ) -> Result<(), Error> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (kind, path, link) in entries {
+            tar_entry(&mut builder, *kind, path, link);
+        }
+        let data = builder.into_inner().unwrap();
+
+        extract_tar(data.as_slice(), &dir.join("root"), ExtractLimits::default())
+    }
+
+    #[test]
+    fn entry_path_rejects_parent_components() {
+        for name in ["../evil", "Payload/../../evil", "Payload/App.app/.."] {
+            assert!(matches!(
+                SafeExtractor::entry_path(Path::new(name)),
+                Err(Error::ArchivePathTraversal(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn entry_path_strips_root_and_current_dir() {
+        assert_eq!(
+            SafeExtractor::entry_path(Path::new("/./Payload/App.app")).unwrap(),
+            Some(PathBuf::from("Payload/App.app"))
+        );
+        assert_eq!(SafeExtractor::entry_path(Path::new("./")).unwrap(), None);
+    }
+
+    #[test]
+    fn zip_rejects_parent_entry() {
+        let dir = scratch();
+        let mut archive = zip_with(|zip| {
+            zip.start_file("../evil", SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"evil").unwrap();
+        });
+
+        let result = extract_zip(&mut archive, &dir.join("root"), ExtractLimits::default());
+        assert!(matches!(result, Err(Error::ArchivePathTraversal(_))));
+        assert!(!dir.join("evil").exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn zip_rejects_escaping_symlink() {
+        let dir = scratch();
+        let mut archive = zip_with(|zip| {
+            zip.add_symlink("Payload/link", "../../evil", SimpleFileOptions::default())
+                .unwrap();
+        });
+
+        let result = extract_zip(&mut archive, &dir.join("root"), ExtractLimits::default());
+        assert!(matches!(result, Err(Error::ArchiveLinkEscapes { .. })));
+        assert!(fs::symlink_metadata(dir.join("root/Payload/link")).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_rejects_absolute_symlink() {
+        let dir = scratch();
+        let result = extract_tar_entries(&dir, &[(tar::EntryType::Symlink, "etc", "/etc")]);
+        assert!(matches!(result, Err(Error::ArchiveLinkEscapes { .. })));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_rejects_symlink_redirected_by_earlier_link() {
+        // `a/b` points at the root, so `a/b/c -> ..` is lexically fine but leaves it
+        let dir = scratch();
+        let result = extract_tar_entries(
+            &dir,
+            &[
+                (tar::EntryType::Directory, "a", ""),
+                (tar::EntryType::Symlink, "a/b", ".."),
+                (tar::EntryType::Symlink, "a/b/c", ".."),
+            ],
+        );
+        assert!(matches!(result, Err(Error::ArchiveLinkEscapes { .. })));
+        assert!(fs::symlink_metadata(dir.join("root/c")).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_rejects_dangling_symlink_below_redirected_parent() {
+        // `a/l/k` really lands at `k`, so `../x` leaves the root even though nothing's there
+        let dir = scratch();
+        let result = extract_tar_entries(
+            &dir,
+            &[
+                (tar::EntryType::Directory, "a", ""),
+                (tar::EntryType::Symlink, "a/l", ".."),
+                (tar::EntryType::Symlink, "a/l/k", "../x"),
+            ],
+        );
+        assert!(matches!(result, Err(Error::ArchiveLinkEscapes { .. })));
+        assert!(fs::symlink_metadata(dir.join("root/k")).is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_keeps_symlink_to_later_entry() {
+        let dir = scratch();
+        extract_tar_entries(
+            &dir,
+            &[
+                (tar::EntryType::Symlink, "a/current", "../b/1.0"),
+                (tar::EntryType::Regular, "b/1.0", ""),
+            ],
+        )
+        .unwrap();
+        assert!(dir.join("root/a/current").is_file());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tar_rejects_writes_through_escaping_directory_link() {
+        // Only possible if a link slipped through, checked again when creating directories
+        let dir = scratch();
+        fs::create_dir_all(dir.join("root")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("root/out")).unwrap();
+
+        let result = extract_tar_entries(&dir, &[(tar::EntryType::Regular, "out/evil", "")]);
+        assert!(matches!(result, Err(Error::ArchivePathTraversal(_))));
+        assert!(!dir.join("evil").exists());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn tar_rejects_hard_link_outside_root() {
+        let dir = scratch();
+        let result = extract_tar_entries(&dir, &[(tar::EntryType::Link, "passwd", "../passwd")]);
+        assert!(matches!(result, Err(Error::ArchivePathTraversal(_))));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+use plume_core::{MachO, MachOExt};
+use serde::Serialize;
+
+use crate::{Bundle, BundleType, Error, PlistInfoTrait};
+
+// Loaded into every UIKit app, whether or not the executable links them directly
+const IMPLICIT_BUNDLES: &[&str] = &[
+    "com.apple.uikit",
+    "com.apple.foundation",
+    "com.apple.corefoundation",
+];
+
+/// A tweak dylib that was left out of executables its filter doesn't target.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedDylib {
+    pub name: String,
+    pub reason: String,
+    /// Whether it was still injected into executables the filter does target.
+    pub partial: bool,
+}
+
+impl std::fmt::Display for SkippedDylib {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.partial {
+            true => write!(f, "Not injecting {} everywhere: {}", self.name, self.reason),
+            false => write!(f, "Skipped {}: {}", self.name, self.reason),
+        }
+    }
+}
+
+/// What a MobileSubstrate filter is matched against.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FilterTarget {
+    bundle_identifier: Option<String>,
+    executable: Option<String>,
+    /// Lowercased identifiers of every bundle the app loads.
+    loaded_bundles: HashSet<String>,
+}
+
+impl FilterTarget {
+    pub(crate) fn from_bundle(bundle: &Bundle) -> Result<Self, Error> {
+        let bundle_identifier = bundle.get_bundle_identifier();
+        let executable = bundle.get_executable();
+
+        let mut loaded_bundles = IMPLICIT_BUNDLES
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<HashSet<_>>();
+
+        loaded_bundles.extend(bundle_identifier.as_ref().map(|b| b.to_lowercase()));
+
+        for nested in bundle.collect_nested_bundles()? {
+            if *nested.bundle_type() == BundleType::Framework {
+                loaded_bundles.extend(nested.get_bundle_identifier().map(|b| b.to_lowercase()));
+            }
+        }
+
+        // `/System/Library/Frameworks/Name.framework/Name` is `com.apple.Name`
+        if let Some(executable) = &executable {
+            let macho = MachO::new(bundle.bundle_dir().join(executable))?;
+            let binary = macho.macho_file().nth_macho(0)?;
+
+            for path in binary.dylib_load_paths()? {
+                if let Some(name) = path
+                    .strip_prefix("/System/Library/Frameworks/")
+                    .and_then(|p| p.split('/').next())
+                    .and_then(|f| f.strip_suffix(".framework"))
+                {
+                    loaded_bundles.insert(format!("com.apple.{}", name.to_lowercase()));
+                }
+            }
+        }
+
+        Ok(Self {
+            bundle_identifier,
+            executable,
+            loaded_bundles,
+        })
+    }
+
+    /// Also matches the identifiers bundles had before `original` was renamed to
+    /// `renamed`, filters name the app's real identifier.
+    pub(crate) fn add_original_identifier(&mut self, original: &str, renamed: &str) {
+        let (original, renamed) = (original.to_lowercase(), renamed.to_lowercase());
+        let originals = self
+            .loaded_bundles
+            .iter()
+            .filter_map(|b| b.strip_prefix(&renamed))
+            .map(|suffix| format!("{}{}", original, suffix))
+            .collect::<Vec<_>>();
+
+        self.loaded_bundles.extend(originals);
+    }
+}
+
+/// The `Filter` dictionary of a MobileSubstrate plist.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SubstrateFilter {
+    bundles: Option<Vec<String>>,
+    executables: Option<Vec<String>>,
+    classes: Option<Vec<String>>,
+    /// `Mode = Any`, one matching category is enough.
+    match_any: bool,
+}
+
+impl SubstrateFilter {
+    /// Reads the filter of a dylib in `MobileSubstrate/DynamicLibraries` from
+    /// the plist next to it, `None` when it has no filter.
+    pub(crate) fn for_dylib(dylib_path: &Path) -> Result<Option<Self>, Error> {
+        let in_dynamic_libraries = dylib_path
+            .parent()
+            .is_some_and(|p| p.ends_with("MobileSubstrate/DynamicLibraries"));
+        let filter_path = dylib_path.with_extension("plist");
+
+        if !in_dynamic_libraries || !filter_path.exists() {
+            return Ok(None);
+        }
+
+        Self::load(&filter_path)
+    }
+
+    /// Reads the filter plist next to a dylib, `None` when it has no filter.
+    pub(crate) fn load(path: &Path) -> Result<Option<Self>, Error> {
+        let value = read_plist(path)?;
+
+        let Some(filter) = value
+            .as_dictionary()
+            .and_then(|d| d.get("Filter"))
+            .and_then(Value::as_dictionary)
+        else {
+            return Ok(None);
+        };
+
+        let strings = |key: &str| {
+            filter.get(key).and_then(Value::as_array).map(|values| {
+                values
+                    .iter()
+                    .filter_map(Value::as_string)
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        Ok(Some(Self {
+            bundles: strings("Bundles"),
+            executables: strings("Executables"),
+            classes: strings("Classes"),
+            match_any: filter.get("Mode").and_then(Value::as_string) == Some("Any"),
+        }))
+    }
+
+    /// Returns why the filter rejects `target`, or `None` if the dylib should load.
+    pub(crate) fn rejects(&self, target: &FilterTarget) -> Option<String> {
+        // Classes can only be checked at runtime, assume they're present
+        if self.match_any && self.classes.is_some() {
+            return None;
+        }
+
+        let mut matched = Vec::new();
+        let mut failed = Vec::new();
+
+        if let Some(bundles) = &self.bundles {
+            let hit = bundles
+                .iter()
+                .any(|b| target.loaded_bundles.contains(&b.to_lowercase()));
+            let description = format!("Bundles [{}]", bundles.join(", "));
+            if hit {
+                matched.push(description);
+            } else {
+                failed.push(description);
+            }
+        }
+
+        if let Some(executables) = &self.executables {
+            let hit = executables
+                .iter()
+                .any(|e| Some(e) == target.executable.as_ref());
+            let description = format!("Executables [{}]", executables.join(", "));
+            if hit {
+                matched.push(description);
+            } else {
+                failed.push(description);
+            }
+        }
+
+        let rejected = if self.match_any {
+            matched.is_empty() && !failed.is_empty()
+        } else {
+            !failed.is_empty()
+        };
+
+        rejected.then(|| {
+            format!(
+                "filter {} doesn't match {} ({})",
+                failed.join(" and "),
+                target.bundle_identifier.as_deref().unwrap_or("???"),
+                target.executable.as_deref().unwrap_or("???"),
+            )
+        })
+    }
+}
+
+/// Reads an XML, binary or OpenStep plist.
+pub(crate) fn read_plist(path: &Path) -> Result<Value, Error> {
+    let data = std::fs::read(path)?;
+
+    // Theos still generates OpenStep plists, which the plist crate can't read
+    Value::from_reader(std::io::Cursor::new(&data))
+        .or_else(|e| parse_openstep(&String::from_utf8_lossy(&data)).ok_or(e))
+        .map_err(Error::from)
+}
+
+impl std::fmt::Display for SubstrateFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let categories = [
+            ("Bundles", &self.bundles),
+            ("Executables", &self.executables),
+            ("Classes", &self.classes),
+        ]
+        .into_iter()
+        .filter_map(|(name, values)| {
+            values
+                .as_ref()
+                .map(|values| format!("{} [{}]", name, values.join(", ")))
+        })
+        .collect::<Vec<_>>();
+
+        let separator = if self.match_any { " or " } else { " and " };
+        write!(f, "{}", categories.join(separator))
+    }
+}
+
+// Minimal OpenStep plist reader, dictionaries, arrays and strings only
+fn parse_openstep(input: &str) -> Option<Value> {
+    let mut chars = input.chars().peekable();
+    let value = parse_openstep_value(&mut chars)?;
+    skip_openstep_whitespace(&mut chars);
+    chars.peek().is_none().then_some(value)
+}
+
+fn parse_openstep_value(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<Value> {
+    skip_openstep_whitespace(chars);
+
+    match chars.peek()? {
+        '{' => {
+            chars.next();
+            let mut dict = Dictionary::new();
+            loop {
+                skip_openstep_whitespace(chars);
+                if chars.peek() == Some(&'}') {
+                    chars.next();
+                    return Some(Value::Dictionary(dict));
+                }
+                let key = parse_openstep_value(chars)?.into_string()?;
+                skip_openstep_whitespace(chars);
+                (chars.next()? == '=').then_some(())?;
+                let value = parse_openstep_value(chars)?;
+                skip_openstep_whitespace(chars);
+                (chars.next()? == ';').then_some(())?;
+                dict.insert(key, value);
+            }
+        }
+        '(' => {
+            chars.next();
+            let mut array = Vec::new();
+            loop {
+                skip_openstep_whitespace(chars);
+                if chars.peek() == Some(&')') {
+                    chars.next();
+                    return Some(Value::Array(array));
+                }
+                array.push(parse_openstep_value(chars)?);
+                skip_openstep_whitespace(chars);
+                match chars.next()? {
+                    ',' => {}
+                    ')' => return Some(Value::Array(array)),
+                    _ => return None,
+                }
+            }
+        }
+        '"' => {
+            chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => return Some(Value::String(string)),
+                    '\\' => string.push(chars.next()?),
+                    c => string.push(c),
+                }
+            }
+        }
+        _ => {
+            let mut string = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || "._-/:$+".contains(c) {
+                    string.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            (!string.is_empty()).then_some(Value::String(string))
+        }
+    }
+}
+
+fn skip_openstep_whitespace(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '/' {
+            // Comments, `// ...` and `/* ... */`
+            let mut lookahead = chars.clone();
+            lookahead.next();
+            match lookahead.next() {
+                Some('/') => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                Some('*') => {
+                    chars.next();
+                    chars.next();
+                    let mut previous = '\0';
+                    for c in chars.by_ref() {
+                        if previous == '*' && c == '/' {
+                            break;
+                        }
+                        previous = c;
+                    }
+                }
+                _ => return,
+            }
+        } else {
+            return;
+        }
+    }
+}
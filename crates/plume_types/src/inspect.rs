@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use plume_core::{MachO, MachOExt};
+use serde::Serialize;
+
+use crate::filter::SubstrateFilter;
+use crate::rewrite::{Resolution, resolve};
+use crate::tweak::TweakItemKind;
+use crate::{
+    Bundle, DebControl, Error, PlistInfoTrait, SignerPathRewrite, UnresolvedDependency,
+    relative_path,
+};
+
+// Injected tweaks find `Frameworks/` through this on the main executable
+const MAIN_RPATH: &str = "@executable_path/Frameworks";
+
+/// What applying a tweak would do to an app.
+#[derive(Debug, Clone, Serialize)]
+pub struct TweakInspection {
+    pub path: String,
+    /// Metadata of a .deb's `control` file.
+    pub control: Option<DebControl>,
+    /// Files copied into the app.
+    pub files: Vec<InspectedFile>,
+    /// PreferenceLoader entries turned into `Settings.bundle` panes.
+    pub preferences: Vec<String>,
+    /// Libraries injected into the main executable and their dependencies.
+    pub binaries: Vec<InspectedBinary>,
+    /// `LC_RPATH` entries added to the main executable.
+    pub rpaths: Vec<String>,
+    /// Jailbreak paths that nothing bundled provides.
+    pub unresolved: Vec<UnresolvedDependency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectedFile {
+    pub kind: TweakItemKind,
+    /// Path inside the package.
+    pub source: String,
+    /// Path inside the app.
+    pub destination: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectedBinary {
+    /// Path inside the app.
+    pub file: String,
+    /// The load command added for it, e.g. `@rpath/Tweak.dylib`.
+    pub load_path: String,
+    pub architectures: Vec<String>,
+    /// Its MobileSubstrate filter, `None` loads it everywhere.
+    pub filter: Option<String>,
+    pub dependencies: Vec<InspectedDependency>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InspectedDependency {
+    pub path: String,
+    pub weak: bool,
+    /// The bundled path it gets rewritten to.
+    pub rewritten: Option<String>,
+    pub unresolved: bool,
+}
+
+impl std::fmt::Display for TweakInspection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.path)?;
+        if let Some(control) = &self.control {
+            writeln!(
+                f,
+                "  Package: {} {} ({})",
+                control.package,
+                control.version,
+                control
+                    .architecture
+                    .as_deref()
+                    .unwrap_or("unknown architecture")
+            )?;
+            for group in &control.depends {
+                let group = group
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" | ");
+                writeln!(f, "  Depends: {}", group)?;
+            }
+        }
+
+        writeln!(f, "Files:")?;
+        for file in &self.files {
+            writeln!(f, "  {} -> {}", file.source, file.destination)?;
+        }
+        for entry in &self.preferences {
+            writeln!(f, "  {} -> Settings.bundle", entry)?;
+        }
+
+        writeln!(f, "Injected:")?;
+        for binary in &self.binaries {
+            writeln!(
+                f,
+                "  {} [{}]",
+                binary.load_path,
+                binary.architectures.join(", ")
+            )?;
+            if let Some(filter) = &binary.filter {
+                writeln!(f, "    Filter: {}", filter)?;
+            }
+            for dependency in &binary.dependencies {
+                let weak = if dependency.weak { " (weak)" } else { "" };
+                match (&dependency.rewritten, dependency.unresolved) {
+                    (Some(rewritten), _) => {
+                        writeln!(f, "    {}{} -> {}", dependency.path, weak, rewritten)?
+                    }
+                    (None, true) => writeln!(f, "    {}{} [unresolved]", dependency.path, weak)?,
+                    (None, false) => writeln!(f, "    {}{}", dependency.path, weak)?,
+                }
+            }
+        }
+
+        for rpath in &self.rpaths {
+            writeln!(f, "Adds rpath: {}", rpath)?;
+        }
+
+        if !self.unresolved.is_empty() {
+            writeln!(f, "Unresolved:")?;
+            for dependency in &self.unresolved {
+                writeln!(f, "  {} (from {})", dependency.path, dependency.binary)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds the inspection of `items`, found in a package extracted to `root`.
+pub(crate) fn inspect_package(
+    package: &Path,
+    root: &Path,
+    items: &[(TweakItemKind, PathBuf)],
+    control: Option<DebControl>,
+    rules: &[SignerPathRewrite],
+    runtime: Option<&str>,
+) -> Result<TweakInspection, Error> {
+    let mut files = Vec::new();
+    // (binary, path in the app, load path)
+    let mut injected = Vec::new();
+
+    for (kind, path) in items {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let destination = kind.destination(&name);
+
+        match kind {
+            TweakItemKind::Dylib => {
+                injected.push((
+                    path.clone(),
+                    destination.clone(),
+                    format!("@rpath/{}", name),
+                ));
+            }
+            TweakItemKind::Framework => {
+                if let Some(executable) = Bundle::new(path).ok().and_then(|b| b.get_executable()) {
+                    injected.push((
+                        path.join(&executable),
+                        format!("{}/{}", destination, executable),
+                        format!("@rpath/{}/{}", name, executable),
+                    ));
+                }
+            }
+            TweakItemKind::Bundle | TweakItemKind::Appex => {}
+        }
+
+        files.push(InspectedFile {
+            kind: *kind,
+            source: relative_path(root, path),
+            destination,
+        });
+    }
+
+    let bundled = injected
+        .iter()
+        .map(|(_, _, load_path)| load_path.clone())
+        .chain(runtime.map(String::from))
+        .collect::<HashSet<_>>();
+    let is_bundled = |rpath: &str| bundled.contains(rpath);
+
+    let mut binaries = Vec::new();
+    let mut unresolved = Vec::new();
+
+    for (path, file, load_path) in injected {
+        let macho = MachO::new(&path)?;
+
+        let mut dependencies = Vec::new();
+        for command in macho.macho_file().nth_macho(0)?.dylib_load_commands()? {
+            let resolution = match command.path.starts_with('/') {
+                true => resolve(&is_bundled, rules, runtime, &command.path),
+                false => Resolution::Keep,
+            };
+
+            let (rewritten, is_unresolved) = match resolution {
+                Resolution::Keep => (None, false),
+                Resolution::Rewrite(rewritten) => (Some(rewritten), false),
+                Resolution::Unresolved => {
+                    unresolved.push(UnresolvedDependency {
+                        binary: file.clone(),
+                        path: command.path.clone(),
+                    });
+                    (None, true)
+                }
+            };
+
+            dependencies.push(InspectedDependency {
+                weak: command.is_weak(),
+                path: command.path,
+                rewritten,
+                unresolved: is_unresolved,
+            });
+        }
+
+        let filter = SubstrateFilter::for_dylib(&path)?.map(|f| f.to_string());
+
+        binaries.push(InspectedBinary {
+            file,
+            load_path,
+            architectures: macho.architectures(),
+            filter,
+            dependencies,
+        });
+    }
+
+    let preferences = ["", "var/jb/"]
+        .iter()
+        .map(|prefix| {
+            root.join(prefix)
+                .join("Library/PreferenceLoader/Preferences")
+        })
+        .filter(|dir| dir.is_dir())
+        .flat_map(|dir| std::fs::read_dir(dir).into_iter().flatten().flatten())
+        .map(|entry| entry.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "plist"))
+        .map(|p| relative_path(root, &p))
+        .collect();
+
+    Ok(TweakInspection {
+        path: package.display().to_string(),
+        control,
+        files,
+        preferences,
+        rpaths: match binaries.is_empty() {
+            true => Vec::new(),
+            false => vec![MAIN_RPATH.to_string()],
+        },
+        binaries,
+        unresolved,
+    })
+}
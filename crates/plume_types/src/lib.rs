@@ -1,26 +1,56 @@
 mod bundle;
+mod compatibility;
+mod control;
 mod device;
+mod entitlements;
+mod extract;
+mod filter;
+mod inspect;
 mod options;
 mod package;
+mod preferences;
+mod quota;
+mod rewrite;
 mod signer;
+mod strings;
+mod thinning;
 mod tweak;
+mod verify;
 
 use std::path::Path;
 
-pub use bundle::{Bundle, BundleType}; // Bundle helper
+pub use bundle::{Bundle, BundleType, LocalizedName, RemovableBundle, RemovableBundleKind}; // Bundle helper
+pub use control::{DebControl, DebDependency, DebRelation, DebRepository, DebResolution}; // .deb metadata
 pub use device::{Device, get_device_for_id, install_app_mac}; // Device helper
+pub use entitlements::{
+    BundleEntitlements, EntitlementEntry, EntitlementReport, EntitlementStatus,
+}; // Entitlement pre-flight
+pub use extract::ExtractLimits; // Safe archive extraction
+pub use filter::SkippedDylib; // MobileSubstrate filters
+pub use inspect::{InspectedBinary, InspectedDependency, InspectedFile, TweakInspection}; // Tweak inspection
 pub use options::{
     SignerApp, // Supported app types
     SignerAppReal,
-    SignerEmbedding,   // Embedding options
-    SignerFeatures,    // Feature support options
-    SignerInstallMode, // Installation mode
-    SignerMode,        // Signing mode
-    SignerOptions,     // Main
+    SignerBundleRemoval, // Nested bundle removal
+    SignerCompression,   // Export compression
+    SignerEmbedding,     // Embedding options
+    SignerFeatures,      // Feature support options
+    SignerHookRuntime,   // Hooking runtime
+    SignerInstallMode,   // Installation mode
+    SignerMode,          // Signing mode
+    SignerOptions,       // Main
+    SignerPathRewrite,   // Jailbreak path rewrites
+    SignerTweak,         // Tweak and its injection targets
+    SignerTweakTarget,
 };
 pub use package::Package; // Package helper
+pub use preferences::UnsupportedPreference; // PreferenceLoader settings
+pub use quota::AppIdQuotaReport; // App ID quota
+pub use rewrite::UnresolvedDependency; // Jailbreak path rewriting
 pub use signer::Signer; // Signer
-pub use tweak::Tweak; // Tweak helper
+pub use thinning::ThinningReport; // Binary thinning
+pub use tweak::{InjectedTweak, Tweak, TweakItemKind}; // Tweak helper
+pub use verify::BundleVerification; // Signature verification
 
 use thiserror::Error as ThisError;
 #[derive(Debug, ThisError)]
@@ -35,8 +65,39 @@ pub enum Error {
     TweakInvalidPath,
     #[error("Tweak extraction failed: {0}")]
     TweakExtractionFailed(String),
+    #[error("No data archive found in .deb")]
+    TweakDataMissing,
+    #[error("Unrecognized data archive format: {0}")]
+    TweakUnsupportedData(String),
+    #[error("Tweak target bundle not found: {0}")]
+    TweakTargetNotFound(String),
+    #[error("No injected tweak matches {0}")]
+    TweakNotInjected(String),
+    #[error("No control file found in .deb")]
+    TweakControlMissing,
+    #[error("Invalid control file in {0}")]
+    TweakControlInvalid(String),
+    #[error("No substrate-compatible library found in {0}")]
+    TweakRuntimeNotFound(String),
     #[error("Unsupported file type: {0}")]
     UnsupportedFileType(String),
+    // Extraction
+    #[error("Archive has more than {0} entries")]
+    ArchiveTooManyEntries(usize),
+    #[error("Archive expands to more than {0} bytes")]
+    ArchiveTooLarge(u64),
+    #[error("Archive entry {path} is larger than {limit} bytes")]
+    ArchiveEntryTooLarge { path: String, limit: u64 },
+    #[error("Archive entry escapes the extraction directory: {0}")]
+    ArchivePathTraversal(String),
+    #[error("Archive link {path} points outside the extraction directory: {target}")]
+    ArchiveLinkEscapes { path: String, target: String },
+    // Signing
+    #[error("FairPlay encrypted binaries crash on launch once resigned: {}", .0.join(", "))]
+    BinariesEncrypted(Vec<String>),
+    // Registration
+    #[error("App ID quota exceeded: {needed} new App IDs needed, {remaining} left this week")]
+    AppIdQuotaExceeded { needed: usize, remaining: usize },
 
     #[error("Zip error: {0}")]
     Zip(#[from] zip::result::ZipError),
@@ -92,3 +153,11 @@ pub async fn copy_dir_recursively(src: &Path, dst: &Path) -> Result<(), Error> {
 
     Ok(())
 }
+
+// `path` below `base`, with `/` separators on every platform
+pub(crate) fn relative_path(base: &Path, path: &Path) -> String {
+    path.strip_prefix(base)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
@@ -15,12 +15,28 @@ pub struct SignerOptions {
     pub mode: SignerMode,
     /// Installation mode.
     pub install_mode: SignerInstallMode,
+    /// Compression used when exporting an IPA.
+    pub compression: SignerCompression,
     /// Tweaks to apply before signing.
-    pub tweaks: Option<Vec<std::path::PathBuf>>,
+    pub tweaks: Option<Vec<SignerTweak>>,
+    /// Limits applied when extracting tweaks.
+    pub extract_limits: crate::ExtractLimits,
+    /// Rewrites for the jailbreak paths tweak binaries link against.
+    pub path_rewrites: Vec<SignerPathRewrite>,
+    /// Folder of .debs or `Packages` index to install tweak dependencies from.
+    pub tweak_repository: Option<std::path::PathBuf>,
+    /// Substrate-compatible runtime tweaks are hooked with.
+    pub hook_runtime: SignerHookRuntime,
+    /// PlugIns, Watch apps and App Clips to strip before registration.
+    pub removed_bundles: SignerBundleRemoval,
+    /// Only keep Mach-O slices this CPU runs, e.g. `arm64e` from a device's `CPUArchitecture`.
+    pub thin_architecture: Option<String>,
     /// App type.
     pub app: SignerApp,
     /// Apply autorefresh
     pub refresh: bool,
+    /// Delete stale plume-created App IDs when the free account quota runs out.
+    pub reuse_stale_app_ids: bool,
 }
 
 impl Default for SignerOptions {
@@ -33,9 +49,17 @@ impl Default for SignerOptions {
             embedding: SignerEmbedding::default(),
             mode: SignerMode::default(),
             install_mode: SignerInstallMode::default(),
+            compression: SignerCompression::default(),
             tweaks: None,
+            extract_limits: crate::ExtractLimits::default(),
+            path_rewrites: SignerPathRewrite::defaults(),
+            tweak_repository: None,
+            hook_runtime: SignerHookRuntime::default(),
+            removed_bundles: SignerBundleRemoval::default(),
+            thin_architecture: None,
             app: SignerApp::Default,
             refresh: false,
+            reuse_stale_app_ids: false,
         }
     }
 }
@@ -68,6 +92,38 @@ pub struct SignerFeatures {
     pub support_liquid_glass: bool,
     pub support_ellekit: bool,
     pub remove_url_schemes: bool,
+    pub remove_supported_devices: bool,
+    pub remove_queries_schemes: bool,
+    pub remove_required_capabilities: bool,
+    /// Thin binaries to the architecture of the device being installed to.
+    pub thin_for_device: bool,
+    /// Remove `SwiftSupport`, `Symbols` and `BCSymbolMaps`.
+    pub strip_symbols: bool,
+}
+
+impl SignerFeatures {
+    /// Info.plist keys that should be scrubbed from every bundle.
+    pub fn info_plist_keys_to_remove(&self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+
+        if self.remove_url_schemes {
+            keys.push("CFBundleURLTypes");
+        }
+
+        if self.remove_supported_devices {
+            keys.push("UISupportedDevices");
+        }
+
+        if self.remove_queries_schemes {
+            keys.push("LSApplicationQueriesSchemes");
+        }
+
+        if self.remove_required_capabilities {
+            keys.push("UIRequiredDeviceCapabilities");
+        }
+
+        keys
+    }
 }
 
 /// Embedding options.
@@ -76,6 +132,31 @@ pub struct SignerEmbedding {
     pub single_profile: bool,
 }
 
+/// Nested bundle removal options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerBundleRemoval {
+    Keep,
+    RemoveAll,
+    /// Paths relative to the main app, e.g. `PlugIns/Share.appex`.
+    Remove(Vec<String>),
+}
+
+impl Default for SignerBundleRemoval {
+    fn default() -> Self {
+        SignerBundleRemoval::Keep
+    }
+}
+
+impl SignerBundleRemoval {
+    pub fn should_remove(&self, relative_path: &str) -> bool {
+        match self {
+            SignerBundleRemoval::Keep => false,
+            SignerBundleRemoval::RemoveAll => true,
+            SignerBundleRemoval::Remove(paths) => paths.iter().any(|p| p == relative_path),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignerInstallMode {
     Install,
@@ -97,6 +178,218 @@ impl std::fmt::Display for SignerInstallMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignerCompression {
+    /// Store entries without compressing them.
+    Store,
+    /// Deflate entries, at the given level (0-9) if set.
+    Deflate(Option<i64>),
+}
+
+impl Default for SignerCompression {
+    fn default() -> Self {
+        SignerCompression::Deflate(None)
+    }
+}
+
+impl std::fmt::Display for SignerCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerCompression::Store => write!(f, "Store"),
+            SignerCompression::Deflate(None) => write!(f, "Deflate"),
+            SignerCompression::Deflate(Some(level)) => write!(f, "Deflate (Level {})", level),
+        }
+    }
+}
+
+/// A tweak file and the bundles it gets injected into.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerTweak {
+    pub path: std::path::PathBuf,
+    pub target: SignerTweakTarget,
+    /// Weak linked tweaks don't stop the app from launching when they fail to load.
+    pub weak: bool,
+}
+
+impl SignerTweak {
+    pub fn new(path: std::path::PathBuf) -> Self {
+        Self {
+            path,
+            target: SignerTweakTarget::default(),
+            weak: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerTweakTarget {
+    /// Only the main app executable.
+    Main,
+    /// The main app and every app extension.
+    All,
+    /// Bundles by path relative to the app, e.g. `PlugIns/Share.appex`.
+    /// An empty path is the main app.
+    Bundles(Vec<String>),
+}
+
+impl Default for SignerTweakTarget {
+    fn default() -> Self {
+        SignerTweakTarget::Main
+    }
+}
+
+impl std::fmt::Display for SignerTweakTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerTweakTarget::Main => write!(f, "Main App"),
+            SignerTweakTarget::All => write!(f, "App & Extensions"),
+            SignerTweakTarget::Bundles(bundles) => {
+                let names = bundles
+                    .iter()
+                    .map(|b| match b.rsplit('/').next() {
+                        Some("") | None => "Main App",
+                        Some(name) => name,
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for SignerTweakTarget {
+    type Err = String;
+
+    /// `main`, `all`, or a comma separated list of bundle paths.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "main" => Ok(SignerTweakTarget::Main),
+            "all" => Ok(SignerTweakTarget::All),
+            "" => Err("tweak target can't be empty".to_string()),
+            list => Ok(SignerTweakTarget::Bundles(
+                list.split(',')
+                    .map(|b| b.trim().trim_matches('/').to_string())
+                    .collect(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerHookRuntime {
+    /// The ElleKit build shipped with plume.
+    BundledElleKit,
+    /// A .deb, .framework or .dylib providing the substrate API, e.g. another ElleKit release.
+    Custom(std::path::PathBuf),
+}
+
+impl Default for SignerHookRuntime {
+    fn default() -> Self {
+        SignerHookRuntime::BundledElleKit
+    }
+}
+
+impl std::fmt::Display for SignerHookRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerHookRuntime::BundledElleKit => write!(f, "Bundled ElleKit"),
+            SignerHookRuntime::Custom(path) => write!(
+                f,
+                "{}",
+                path.file_name()
+                    .map(|n| n.to_string_lossy())
+                    .unwrap_or_else(|| path.to_string_lossy())
+            ),
+        }
+    }
+}
+
+/// Maps a jailbreak install path to the `@rpath` path it's bundled at.
+///
+/// A `from` ending in `/` is a prefix, the rest of the path is appended to `to`.
+/// Paths under `/var/jb` are matched by their rootful equivalent. A `to` of
+/// [`SignerPathRewrite::HOOK_RUNTIME`] is wherever the hooking runtime got installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignerPathRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+impl SignerPathRewrite {
+    pub const HOOK_RUNTIME: &str = "@runtime";
+
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    pub fn is_prefix(&self) -> bool {
+        self.from.ends_with('/')
+    }
+
+    /// Hooking libraries go to the hooking runtime, everything else to `Frameworks/`.
+    pub fn defaults() -> Vec<Self> {
+        let mut rewrites = [
+            "/Library/Frameworks/CydiaSubstrate.framework/CydiaSubstrate",
+            "/usr/lib/libsubstrate.dylib",
+            "/usr/lib/libellekit.dylib",
+            "/usr/lib/libhooker.dylib",
+            "/usr/lib/libblackjack.dylib",
+        ]
+        .into_iter()
+        .map(|from| Self::new(from, Self::HOOK_RUNTIME))
+        .collect::<Vec<_>>();
+
+        rewrites.extend(
+            [
+                "libcolorpicker.dylib",
+                "libsparkcolourpicker.dylib",
+                "libsparkapplist.dylib",
+                "librocketbootstrap.dylib",
+                "libactivator.dylib",
+            ]
+            .into_iter()
+            .map(|name| Self::new(format!("/usr/lib/{}", name), format!("@rpath/{}", name))),
+        );
+
+        rewrites.extend([
+            Self::new("/Library/Frameworks/", "@rpath/"),
+            Self::new("/Library/MobileSubstrate/DynamicLibraries/", "@rpath/"),
+            Self::new("/usr/lib/", "@rpath/"),
+        ]);
+
+        rewrites
+    }
+
+    /// The rewritten path if this rule matches `path`.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        if self.is_prefix() {
+            path.strip_prefix(&self.from)
+                .filter(|rest| !rest.is_empty())
+                .map(|rest| format!("{}{}", self.to, rest))
+        } else {
+            (path == self.from).then(|| self.to.clone())
+        }
+    }
+}
+
+impl std::str::FromStr for SignerPathRewrite {
+    type Err = String;
+
+    /// `from=to`, e.g. `/usr/lib/libfoo.dylib=@rpath/libfoo.dylib`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((from, to)) if from.starts_with('/') && !to.is_empty() => Ok(Self::new(from, to)),
+            _ => Err(format!(
+                "invalid rewrite '{}', expected /absolute/path=@rpath/path",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignerMode {
     Pem,
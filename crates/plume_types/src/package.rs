@@ -1,11 +1,21 @@
 use super::{Bundle, PlistInfoTrait};
-use crate::{Error, SignerApp, SignerOptions};
+use crate::bundle::is_macho_magic;
+use crate::extract::extract_zip;
+use crate::{
+    Error, ExtractLimits, RemovableBundle, RemovableBundleKind, SignerApp, SignerCompression,
+    SignerOptions,
+};
 use plist::Dictionary;
-use std::path::PathBuf;
+use plume_core::MachO;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::{env, fs, io::Read};
 use uuid::Uuid;
 use zip::ZipArchive;
-use zip::write::FileOptions;
+use zip::write::SimpleFileOptions;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
 
 #[derive(Debug, Clone)]
 pub struct Package {
@@ -14,10 +24,15 @@ pub struct Package {
     stage_payload_dir: PathBuf,
     info_plist_dictionary: Dictionary,
     archive_entries: Vec<String>,
+    extract_limits: ExtractLimits,
 }
 
 impl Package {
     pub fn new(package_file: PathBuf) -> Result<Self, Error> {
+        Self::new_with_limits(package_file, ExtractLimits::default())
+    }
+
+    pub fn new_with_limits(package_file: PathBuf, limits: ExtractLimits) -> Result<Self, Error> {
         let stage_dir = env::temp_dir().join(format!(
             "plume_stage_{:08}",
             Uuid::new_v4().to_string().to_uppercase()
@@ -29,6 +44,10 @@ impl Package {
 
         let file = fs::File::open(&out_package_file)?;
         let mut archive = ZipArchive::new(file)?;
+        if archive.len() > limits.max_entries {
+            return Err(Error::ArchiveTooManyEntries(limits.max_entries));
+        }
+
         let archive_entries = (0..archive.len())
             .filter_map(|i| archive.by_index(i).ok().map(|f| f.name().to_string()))
             .collect::<Vec<_>>();
@@ -42,9 +61,15 @@ impl Package {
             stage_payload_dir: stage_dir.join("Payload"),
             info_plist_dictionary,
             archive_entries,
+            extract_limits: limits,
         })
     }
 
+    /// The same package with a stage of its own, so extracting it doesn't touch this one's.
+    pub fn restage(&self) -> Result<Self, Error> {
+        Self::new_with_limits(self.package_file.clone(), self.extract_limits)
+    }
+
     pub fn package_file(&self) -> &PathBuf {
         &self.package_file
     }
@@ -72,10 +97,91 @@ impl Package {
         Ok(plist::from_bytes(&plist_data)?)
     }
 
+    /// Lists the PlugIns, Watch apps and App Clips without extracting the archive.
+    pub fn get_removable_bundles(&self) -> Result<Vec<RemovableBundle>, Error> {
+        let file = fs::File::open(&self.package_file)?;
+        let mut archive = ZipArchive::new(file)?;
+        let mut removable = Vec::new();
+
+        for entry in &self.archive_entries {
+            // Payload/App.app/PlugIns/Share.appex/Info.plist
+            let parts = entry.split('/').collect::<Vec<_>>();
+            if parts.len() != 5 || parts[0] != "Payload" || parts[4] != "Info.plist" {
+                continue;
+            }
+
+            let relative_path = format!("{}/{}", parts[2], parts[3]);
+            let Some(kind) = RemovableBundleKind::from_relative_path(&relative_path) else {
+                continue;
+            };
+
+            let mut plist_data = Vec::new();
+            archive.by_name(entry)?.read_to_end(&mut plist_data)?;
+            let info = plist::from_bytes::<Dictionary>(&plist_data).unwrap_or_default();
+            let get_string = |key: &str| {
+                info.get(key)
+                    .and_then(|v| v.as_string())
+                    .map(|s| s.to_string())
+            };
+
+            removable.push(RemovableBundle {
+                kind,
+                path: relative_path,
+                name: get_string("CFBundleDisplayName").or_else(|| get_string("CFBundleName")),
+                identifier: get_string("CFBundleIdentifier"),
+            });
+        }
+
+        removable.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(removable)
+    }
+
+    /// Binaries still FairPlay encrypted, relative to the app, read without extracting the archive.
+    /// These come from the App Store and crash on launch once resigned.
+    pub fn get_encrypted_binaries(&self) -> Result<Vec<String>, Error> {
+        let file = fs::File::open(&self.package_file)?;
+        let mut archive = ZipArchive::new(file)?;
+        let mut encrypted = Vec::new();
+
+        for entry in &self.archive_entries {
+            // Payload/App.app/Frameworks/Foo.framework/Foo
+            let Some((_, relative_path)) = entry
+                .strip_prefix("Payload/")
+                .and_then(|p| p.split_once('/'))
+            else {
+                continue;
+            };
+
+            // Executables have no extension, dylibs are the exception
+            let name = relative_path.rsplit('/').next().unwrap_or_default();
+            if name.is_empty() || (name.contains('.') && !name.ends_with(".dylib")) {
+                continue;
+            }
+
+            let mut file = archive.by_name(entry)?;
+            if file.is_dir() || file.size() > self.extract_limits.max_entry_size {
+                continue;
+            }
+
+            let mut magic = [0u8; 4];
+            if file.read_exact(&mut magic).is_err() || !is_macho_magic(magic) {
+                continue;
+            }
+
+            // Only the headers are decompressed, not the whole binary
+            if MachO::is_encrypted_reader(magic.as_slice().chain(&mut file)).unwrap_or(false) {
+                encrypted.push(relative_path.to_string());
+            }
+        }
+
+        Ok(encrypted)
+    }
+
     pub fn get_package_bundle(&self) -> Result<Bundle, Error> {
         let file = fs::File::open(&self.package_file)?;
         let mut archive = ZipArchive::new(file)?;
-        archive.extract(&self.stage_dir)?;
+        extract_zip(&mut archive, &self.stage_dir, self.extract_limits)?;
 
         let app_dir = fs::read_dir(&self.stage_payload_dir)?
             .filter_map(Result::ok)
@@ -86,19 +192,32 @@ impl Package {
         Ok(Bundle::new(app_dir)?)
     }
 
-    pub fn get_archive_based_on_path(&self, path: &PathBuf) -> Result<PathBuf, Error> {
+    pub fn get_archive_based_on_path(
+        &self,
+        path: &PathBuf,
+        compression: SignerCompression,
+    ) -> Result<PathBuf, Error> {
         if path.is_dir() {
-            self.clone().archive_package_bundle()
+            self.clone().archive_package_bundle(compression)
         } else {
             Ok(self.package_file.clone())
         }
     }
 
-    fn archive_package_bundle(self) -> Result<PathBuf, Error> {
+    fn archive_package_bundle(self, compression: SignerCompression) -> Result<PathBuf, Error> {
         let zip_file_path = self.stage_dir.join("resigned.ipa");
+        // Modes of the input entries, so the export keeps its attributes and symlinks
+        let original_modes = self.get_archive_modes()?;
         let file = fs::File::create(&zip_file_path)?;
         let mut zip = zip::ZipWriter::new(file);
-        let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let options = match compression {
+            SignerCompression::Store => {
+                SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored)
+            }
+            SignerCompression::Deflate(level) => SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .compression_level(level),
+        };
 
         let payload_dir = self.stage_payload_dir;
 
@@ -106,40 +225,103 @@ impl Package {
             zip: &mut zip::ZipWriter<fs::File>,
             path: &PathBuf,
             prefix: &PathBuf,
-            options: &FileOptions<'_, zip::write::ExtendedFileOptions>,
+            options: SimpleFileOptions,
+            original_modes: &HashMap<String, u32>,
         ) -> Result<(), Error> {
-            for entry in fs::read_dir(path)? {
-                let entry = entry?;
+            let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+            entries.sort_by_key(|e| e.file_name());
+
+            for entry in entries {
                 let entry_path = entry.path();
                 let name = entry_path
                     .strip_prefix(prefix)
                     .map_err(|_| Error::PackageInfoPlistMissing)?
                     .to_string_lossy()
-                    .to_string();
+                    .replace('\\', "/");
+
+                // Don't follow symlinks, they'd be archived as copies
+                let file_type = entry.file_type()?;
+                let original_mode = original_modes.get(&name).copied();
+                let was_symlink = original_mode.is_some_and(|m| m & S_IFMT == S_IFLNK);
 
-                if entry_path.is_file() {
-                    zip.start_file(&name, options.clone())?;
+                if file_type.is_symlink() || was_symlink {
+                    let target = if file_type.is_symlink() {
+                        fs::read_link(&entry_path)?
+                            .to_string_lossy()
+                            .replace('\\', "/")
+                    } else {
+                        // Extracted as a plain file holding the target (e.g. on Windows)
+                        fs::read_to_string(&entry_path)?
+                    };
+                    let mode = original_mode.unwrap_or(0o777);
+                    zip.add_symlink(&name, target, options.unix_permissions(mode))?;
+                } else if file_type.is_dir() {
+                    let mode = entry_mode(original_mode, &entry_path, 0o755);
+                    zip.add_directory(&name, options.unix_permissions(mode))?;
+                    add_dir_to_zip(zip, &entry_path, prefix, options, original_modes)?;
+                } else {
+                    let mode = entry_mode(original_mode, &entry_path, 0o644);
+                    let large_file = entry.metadata()?.len() >= u32::MAX as u64;
+                    zip.start_file(&name, options.unix_permissions(mode).large_file(large_file))?;
                     let mut f = fs::File::open(&entry_path)?;
                     std::io::copy(&mut f, zip)?;
-                } else if entry_path.is_dir() {
-                    zip.add_directory(&name, options.clone())?;
-                    add_dir_to_zip(zip, &entry_path, prefix, options)?;
                 }
             }
             Ok(())
         }
 
-        add_dir_to_zip(&mut zip, &payload_dir, &self.stage_dir, &options)?;
+        add_dir_to_zip(
+            &mut zip,
+            &payload_dir,
+            &self.stage_dir,
+            options,
+            &original_modes,
+        )?;
         zip.finish()?;
 
         Ok(zip_file_path)
     }
 
+    fn get_archive_modes(&self) -> Result<HashMap<String, u32>, Error> {
+        let file = fs::File::open(&self.package_file)?;
+        let mut archive = ZipArchive::new(file)?;
+        let mut modes = HashMap::new();
+
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i)?;
+            if let Some(mode) = entry.unix_mode() {
+                modes.insert(entry.name().trim_end_matches('/').to_string(), mode);
+            }
+        }
+
+        Ok(modes)
+    }
+
     pub fn remove_package_stage(self) {
         fs::remove_dir_all(&self.stage_dir).ok();
     }
 }
 
+// Prefer the input archive's mode, then whatever is on disk
+fn entry_mode(original_mode: Option<u32>, path: &Path, default: u32) -> u32 {
+    if let Some(mode) = original_mode {
+        return mode;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = fs::metadata(path) {
+            return metadata.permissions().mode();
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = path;
+
+    default
+}
+
 // TODO: make bundle and package share a common trait for plist info access
 macro_rules! get_plist_dict_value {
     ($self:ident, $key:expr) => {{
@@ -0,0 +1,345 @@
+use std::fs;
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+use crate::filter::read_plist;
+use crate::{Bundle, Error, PlistInfoTrait};
+
+const PREFERENCE_LOADER_DIR: &str = "Library/PreferenceLoader/Preferences";
+const PREFERENCE_BUNDLES_DIR: &str = "Library/PreferenceBundles";
+const SETTINGS_BUNDLE: &str = "Settings.bundle";
+const TWEAKS_GROUP: &str = "Tweaks";
+
+/// A PreferenceLoader entry turned into a child pane of the app's `Settings.bundle`.
+#[derive(Debug, Clone)]
+pub(crate) struct PreferencePane {
+    /// Name of the pane's plist in `Settings.bundle`, without extension.
+    pub file: String,
+    pub title: String,
+}
+
+/// A PreferenceLoader entry whose settings can't be changed from the app.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnsupportedPreference {
+    pub title: String,
+    pub reason: String,
+}
+
+impl std::fmt::Display for UnsupportedPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} settings aren't available: {}",
+            self.title, self.reason
+        )
+    }
+}
+
+/// Whether a tweak's package ships PreferenceLoader entries below `root`.
+pub(crate) fn has_preferences(root: &Path) -> bool {
+    root.join(PREFERENCE_LOADER_DIR).is_dir()
+}
+
+/// Converts the PreferenceLoader entries below `root` into panes of the app's
+/// `Settings.bundle`, so tweak settings show up under the app in Settings.
+///
+/// Custom controllers and cells can't run there, only the standard cells
+/// Settings supports are kept. Settings only writes the app's own defaults and
+/// can't post `PostNotification`, so values kept in the tweak's own `defaults`
+/// domain are left out and returned as unsupported instead.
+pub(crate) fn install_preferences(
+    root: &Path,
+    app_bundle: &Path,
+) -> Result<(Vec<PreferencePane>, Vec<UnsupportedPreference>), Error> {
+    let settings_dir = app_bundle.join(SETTINGS_BUNDLE);
+    let app_domain = Bundle::new(app_bundle)?.get_bundle_identifier();
+    let mut panes = Vec::new();
+    let mut unsupported = Vec::new();
+
+    let mut pending = vec![root.join(PREFERENCE_LOADER_DIR)];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|e| e == "plist") {
+                let entry = install_entry(
+                    root,
+                    &path,
+                    &settings_dir,
+                    app_domain.as_deref(),
+                    &mut unsupported,
+                )?;
+                match entry {
+                    Some(pane) => panes.push(pane),
+                    None => log::info!("No usable settings in {}", path.display()),
+                }
+            }
+        }
+    }
+
+    if !panes.is_empty() {
+        add_child_panes(&settings_dir, &panes)?;
+    }
+
+    Ok((panes, unsupported))
+}
+
+fn install_entry(
+    root: &Path,
+    entry_path: &Path,
+    settings_dir: &Path,
+    app_domain: Option<&str>,
+    unsupported: &mut Vec<UnsupportedPreference>,
+) -> Result<Option<PreferencePane>, Error> {
+    let Ok(loader) = read_plist(entry_path) else {
+        return Ok(None);
+    };
+    let Some(entry) = loader
+        .as_dictionary()
+        .and_then(|d| d.get("entry"))
+        .and_then(Value::as_dictionary)
+    else {
+        return Ok(None);
+    };
+
+    let stem = entry_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = entry
+        .get("label")
+        .and_then(Value::as_string)
+        .unwrap_or(stem.as_str())
+        .to_string();
+
+    // Simple entries list their items inline, the rest point at a preference bundle
+    let (items, bundle_dir) = match loader.as_dictionary().and_then(|d| d.get("items")) {
+        Some(items) => (items.clone(), None),
+        None => {
+            let Some(bundle) = entry.get("bundle").and_then(Value::as_string) else {
+                return Ok(None);
+            };
+            let bundle_dir = root
+                .join(PREFERENCE_BUNDLES_DIR)
+                .join(format!("{}.bundle", bundle));
+            let Ok(specifiers) = read_plist(&bundle_dir.join("Root.plist")) else {
+                return Ok(None);
+            };
+            let Some(items) = specifiers.as_dictionary().and_then(|d| d.get("items")) else {
+                return Ok(None);
+            };
+            (items.clone(), Some(bundle_dir))
+        }
+    };
+
+    let items = items.as_array().map(Vec::as_slice).unwrap_or_default();
+
+    // Without `defaults` a value goes to the app's domain, like Settings writes it
+    let domain_of = |item: &Dictionary| item.get("defaults").and_then(Value::as_string);
+    let mut foreign_domains = Vec::new();
+    for item in items.iter().filter_map(Value::as_dictionary) {
+        if let Some(domain) = domain_of(item).filter(|d| Some(*d) != app_domain) {
+            if !foreign_domains.contains(&domain) {
+                foreign_domains.push(domain);
+            }
+        }
+    }
+
+    let specifiers = items
+        .iter()
+        .filter_map(Value::as_dictionary)
+        .filter(|&item| domain_of(item).is_none_or(|d| Some(d) == app_domain))
+        .filter_map(convert_specifier)
+        .map(Value::Dictionary)
+        .collect::<Vec<_>>();
+
+    if items.len() > specifiers.len() {
+        log::info!(
+            "{}: {} of {} settings can't be shown by Settings and were left out",
+            title,
+            items.len() - specifiers.len(),
+            items.len()
+        );
+    }
+
+    if !foreign_domains.is_empty() {
+        unsupported.push(UnsupportedPreference {
+            title: title.clone(),
+            reason: format!(
+                "the tweak reads them from {}, Settings only writes the app's own defaults",
+                foreign_domains.join(", ")
+            ),
+        });
+    }
+
+    if !specifiers
+        .iter()
+        .any(|s| s.as_dictionary().and_then(|d| d.get("Key")).is_some())
+    {
+        if foreign_domains.is_empty() {
+            unsupported.push(UnsupportedPreference {
+                title,
+                reason: "it only uses custom controllers or cells".to_string(),
+            });
+        }
+        return Ok(None);
+    }
+
+    let file = format!(
+        "Tweak_{}",
+        stem.replace(|c: char| !c.is_alphanumeric(), "_")
+    );
+    fs::create_dir_all(settings_dir)?;
+
+    let mut pane = Dictionary::new();
+    pane.insert("Title".into(), title.clone().into());
+    pane.insert("PreferenceSpecifiers".into(), Value::Array(specifiers));
+    if let Some(bundle_dir) = bundle_dir {
+        if copy_strings(&bundle_dir, settings_dir, &file)? {
+            pane.insert("StringsTable".into(), file.clone().into());
+        }
+    }
+    Value::Dictionary(pane).to_file_xml(settings_dir.join(format!("{}.plist", file)))?;
+
+    Ok(Some(PreferencePane { file, title }))
+}
+
+// PreferenceLoader cells Settings has an equivalent for
+fn convert_specifier(item: &Dictionary) -> Option<Dictionary> {
+    let cell = item.get("cell")?.as_string()?;
+    let kind = match cell {
+        "PSGroupCell" => "PSGroupSpecifier",
+        "PSSwitchCell" => "PSToggleSwitchSpecifier",
+        "PSEditTextCell" | "PSSecureEditTextCell" => "PSTextFieldSpecifier",
+        "PSSliderCell" => "PSSliderSpecifier",
+        "PSLinkListCell" | "PSSegmentCell" => "PSMultiValueSpecifier",
+        _ => return None,
+    };
+
+    let mut specifier = Dictionary::new();
+    specifier.insert("Type".into(), kind.into());
+
+    // `default` is the fallback for keys the cell doesn't have
+    let copy = |specifier: &mut Dictionary, from: &str, to: &str, default: Option<Value>| {
+        if let Some(value) = item.get(from).cloned().or(default) {
+            specifier.insert(to.into(), value);
+        }
+    };
+    copy(&mut specifier, "label", "Title", None);
+
+    if kind == "PSGroupSpecifier" {
+        copy(&mut specifier, "footerText", "FooterText", None);
+        return Some(specifier);
+    }
+
+    // Anything that stores a value needs a key, custom controllers often save it themselves
+    item.get("key")?;
+    copy(&mut specifier, "key", "Key", None);
+
+    match kind {
+        "PSTextFieldSpecifier" => {
+            copy(&mut specifier, "default", "DefaultValue", None);
+            if cell == "PSSecureEditTextCell" {
+                specifier.insert("IsSecure".into(), true.into());
+            }
+        }
+        "PSSliderSpecifier" => {
+            copy(&mut specifier, "min", "MinimumValue", Some(0.0.into()));
+            copy(&mut specifier, "max", "MaximumValue", Some(1.0.into()));
+            copy(&mut specifier, "default", "DefaultValue", Some(0.0.into()));
+        }
+        "PSMultiValueSpecifier" => {
+            let values = item.get("validValues")?.as_array()?;
+            copy(&mut specifier, "validTitles", "Titles", None);
+            copy(&mut specifier, "validValues", "Values", None);
+            copy(&mut specifier, "shortTitles", "ShortTitles", None);
+            copy(
+                &mut specifier,
+                "default",
+                "DefaultValue",
+                values.first().cloned(),
+            );
+            specifier.contains_key("Titles").then_some(())?;
+        }
+        _ => copy(
+            &mut specifier,
+            "default",
+            "DefaultValue",
+            Some(false.into()),
+        ),
+    }
+
+    Some(specifier)
+}
+
+// `*.lproj/Root.strings` of the preference bundle, renamed to the pane's table
+fn copy_strings(bundle_dir: &Path, settings_dir: &Path, table: &str) -> Result<bool, Error> {
+    let mut copied = false;
+
+    for entry in fs::read_dir(bundle_dir)? {
+        let lproj = entry?.path();
+        let strings = lproj.join("Root.strings");
+        if lproj.extension().is_some_and(|e| e == "lproj") && strings.is_file() {
+            let dest = settings_dir.join(lproj.file_name().unwrap_or_default());
+            fs::create_dir_all(&dest)?;
+            fs::copy(&strings, dest.join(format!("{}.strings", table)))?;
+            copied = true;
+        }
+    }
+
+    Ok(copied)
+}
+
+// Links every pane from `Root.plist` under a "Tweaks" group, keeping what the app had
+fn add_child_panes(settings_dir: &Path, panes: &[PreferencePane]) -> Result<(), Error> {
+    let root_path = settings_dir.join("Root.plist");
+    let mut root = if root_path.exists() {
+        Value::from_file(&root_path)?
+    } else {
+        Value::Dictionary(Dictionary::new())
+    };
+
+    let invalid = || Error::Other(format!("{} is not a settings plist", root_path.display()));
+    let root_dict = root.as_dictionary_mut().ok_or_else(invalid)?;
+    if !root_dict.contains_key("PreferenceSpecifiers") {
+        root_dict.insert("PreferenceSpecifiers".into(), Value::Array(Vec::new()));
+    }
+    let specifiers = root_dict
+        .get_mut("PreferenceSpecifiers")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(invalid)?;
+
+    let has_group = specifiers.iter().filter_map(Value::as_dictionary).any(|s| {
+        s.get("Type").and_then(Value::as_string) == Some("PSGroupSpecifier")
+            && s.get("Title").and_then(Value::as_string) == Some(TWEAKS_GROUP)
+    });
+    if !has_group {
+        let mut group = Dictionary::new();
+        group.insert("Type".into(), "PSGroupSpecifier".into());
+        group.insert("Title".into(), TWEAKS_GROUP.into());
+        specifiers.push(Value::Dictionary(group));
+    }
+
+    for pane in panes {
+        let exists = specifiers
+            .iter()
+            .filter_map(Value::as_dictionary)
+            .any(|s| s.get("File").and_then(Value::as_string) == Some(&pane.file));
+        if exists {
+            continue;
+        }
+
+        let mut child = Dictionary::new();
+        child.insert("Type".into(), "PSChildPaneSpecifier".into());
+        child.insert("Title".into(), pane.title.clone().into());
+        child.insert("File".into(), pane.file.clone().into());
+        specifiers.push(Value::Dictionary(child));
+    }
+
+    root.to_file_xml(&root_path)?;
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use plume_core::developer::DeveloperSession;
+use plume_store::{AccountStore, AppIdQuota, RegisteredAppId};
+
+use crate::{Bundle, BundleType, Error, PlistInfoTrait, Signer};
+
+/// App ID usage for a bundle that is about to be registered.
+#[derive(Debug, Clone)]
+pub struct AppIdQuotaReport {
+    pub quota: AppIdQuota,
+    /// Identifiers that don't have an App ID on the team yet.
+    pub needed: Vec<String>,
+    /// Plume-created App IDs that can be deleted to make room, oldest first.
+    pub stale: Vec<RegisteredAppId>,
+}
+
+impl AppIdQuotaReport {
+    /// How many App IDs are missing for registration to succeed.
+    pub fn shortfall(&self) -> usize {
+        self.needed.len().saturating_sub(self.quota.remaining())
+    }
+}
+
+impl Signer {
+    /// Checks how many new App IDs `register_bundle` would create against the
+    /// free account quota tracked in `store`.
+    pub async fn app_id_quota(
+        &self,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &String,
+        store: &AccountStore,
+    ) -> Result<AppIdQuotaReport, Error> {
+        let identifiers = self.registered_identifiers(bundle)?;
+        let existing = session.qh_list_app_ids(team_id).await?.app_ids;
+
+        let needed = identifiers
+            .iter()
+            .filter(|id| !existing.iter().any(|a| &a.identifier == *id))
+            .cloned()
+            .collect();
+
+        // Only offer App IDs that still exist on the team
+        let stale = store
+            .stale_app_ids(team_id, &identifiers)
+            .into_iter()
+            .filter(|a| existing.iter().any(|e| e.app_id_id == a.app_id_id))
+            .collect();
+
+        Ok(AppIdQuotaReport {
+            quota: store.app_id_quota(team_id),
+            needed,
+            stale,
+        })
+    }
+
+    /// Deletes just enough stale plume-created App IDs to cover the report's shortfall.
+    pub async fn reclaim_stale_app_ids(
+        &self,
+        session: &DeveloperSession,
+        team_id: &String,
+        store: &mut AccountStore,
+        report: &AppIdQuotaReport,
+    ) -> Result<usize, Error> {
+        let mut reclaimed = 0;
+
+        for app_id in report.stale.iter().take(report.shortfall()) {
+            log::info!("Reusing stale App ID slot: {}", app_id.identifier);
+            session.qh_delete_app_id(team_id, &app_id.app_id_id).await?;
+            store.remove_app_id(&app_id.app_id_id).await?;
+            reclaimed += 1;
+        }
+
+        Ok(reclaimed)
+    }
+
+    /// Makes sure registration won't run out of App IDs halfway through,
+    /// reusing stale slots when `reuse_stale_app_ids` is set.
+    ///
+    /// Paid teams have no such quota, so `None` is returned for them.
+    pub async fn ensure_app_id_quota(
+        &self,
+        bundle: &Bundle,
+        session: &DeveloperSession,
+        team_id: &String,
+        store: &mut AccountStore,
+    ) -> Result<Option<AppIdQuota>, Error> {
+        if !session.qh_team_membership(team_id).await?.is_free() {
+            return Ok(None);
+        }
+
+        let mut report = self.app_id_quota(bundle, session, team_id, store).await?;
+
+        if report.shortfall() > 0 && self.options.reuse_stale_app_ids {
+            self.reclaim_stale_app_ids(session, team_id, store, &report)
+                .await?;
+            report = self.app_id_quota(bundle, session, team_id, store).await?;
+        }
+
+        if report.shortfall() > 0 {
+            return Err(Error::AppIdQuotaExceeded {
+                needed: report.needed.len(),
+                remaining: report.quota.remaining(),
+            });
+        }
+
+        Ok(Some(report.quota))
+    }
+
+    fn registered_identifiers(&self, bundle: &Bundle) -> Result<Vec<String>, Error> {
+        Ok(bundle
+            .collect_bundles_sorted()?
+            .into_iter()
+            .filter(|b| matches!(b.bundle_type(), BundleType::App | BundleType::AppExtension))
+            .filter(|b| {
+                !self.options.embedding.single_profile || b.bundle_dir() == bundle.bundle_dir()
+            })
+            .filter_map(|b| b.get_bundle_identifier())
+            .collect())
+    }
+}
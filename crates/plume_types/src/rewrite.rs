@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use plume_core::{MachO, MachOExt};
+use serde::Serialize;
+
+use crate::{Error, SignerPathRewrite, relative_path};
+
+// Rootless jailbreaks install everything under one of these
+const ROOTLESS_PREFIXES: &[&str] = &["/private/var/jb", "/var/jb"];
+
+// Only jailbreaks put anything here, `/usr/lib` is shared with the system
+const JAILBREAK_PREFIXES: &[&str] = &["/Library/", "/usr/local/"];
+
+/// A library a tweak binary links against that isn't bundled in the app.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedDependency {
+    /// The binary linking it, relative to the app.
+    pub binary: String,
+    /// The load command path, e.g. `/var/jb/usr/lib/libfoo.dylib`.
+    pub path: String,
+}
+
+impl std::fmt::Display for UnresolvedDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} links {}, which isn't bundled",
+            self.binary, self.path
+        )
+    }
+}
+
+pub(crate) enum Resolution {
+    Keep,
+    Rewrite(String),
+    Unresolved,
+}
+
+/// Where a load command path should point, `is_bundled` checks an `@rpath` path.
+pub(crate) fn resolve(
+    is_bundled: &dyn Fn(&str) -> bool,
+    rules: &[SignerPathRewrite],
+    runtime: Option<&str>,
+    path: &str,
+) -> Resolution {
+    let rootful = ROOTLESS_PREFIXES
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix).filter(|p| p.starts_with('/')));
+    let is_jailbreak_path =
+        rootful.is_some() || JAILBREAK_PREFIXES.iter().any(|p| path.starts_with(p));
+    let path = rootful.unwrap_or(path);
+
+    // Exact rules name known jailbreak libraries, prefixes only guess where one went
+    let exact = rules
+        .iter()
+        .filter(|r| !r.is_prefix())
+        .find_map(|r| r.apply(path))
+        .map(|to| match runtime {
+            Some(runtime) if to == SignerPathRewrite::HOOK_RUNTIME => runtime.to_string(),
+            _ => to,
+        });
+    let guessed = rules
+        .iter()
+        .filter(|r| r.is_prefix())
+        .find_map(|r| r.apply(path));
+
+    match (exact, guessed) {
+        (Some(rewritten), _) if is_bundled(rewritten.as_str()) => Resolution::Rewrite(rewritten),
+        (Some(_), _) => Resolution::Unresolved,
+        (None, Some(rewritten)) if is_bundled(rewritten.as_str()) => Resolution::Rewrite(rewritten),
+        _ if is_jailbreak_path => Resolution::Unresolved,
+        _ => Resolution::Keep,
+    }
+}
+
+/// Points every jailbreak path `binaries` link against at its bundled copy,
+/// returning the ones that aren't bundled. `runtime` is the hooking runtime's load path.
+pub(crate) fn rewrite_dependencies(
+    app_bundle: &Path,
+    binaries: &[PathBuf],
+    rules: &[SignerPathRewrite],
+    runtime: Option<&str>,
+) -> Result<Vec<UnresolvedDependency>, Error> {
+    let frameworks_dir = app_bundle.join("Frameworks");
+    let is_bundled = |rpath: &str| {
+        rpath
+            .strip_prefix("@rpath/")
+            .is_some_and(|p| frameworks_dir.join(p).exists())
+    };
+    let mut unresolved = Vec::new();
+
+    for binary in binaries {
+        let macho = MachO::new(binary)?;
+
+        let mut paths = Vec::new();
+        for slice in macho.macho_file().iter_macho() {
+            for path in slice.dylib_load_paths()? {
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        for path in paths.into_iter().filter(|p| p.starts_with('/')) {
+            match resolve(&is_bundled, rules, runtime, &path) {
+                Resolution::Keep => {}
+                Resolution::Rewrite(rewritten) => {
+                    log::info!(
+                        "Rewriting {} to {} in {}",
+                        path,
+                        rewritten,
+                        binary.display()
+                    );
+                    // Reparse, offsets move when a command has to grow
+                    MachO::new(binary)?.replace_dylib(&path, &rewritten)?;
+                }
+                Resolution::Unresolved => {
+                    let binary = relative_path(app_bundle, binary);
+                    let dependency = UnresolvedDependency { binary, path };
+                    log::warn!("{}", dependency);
+
+                    unresolved.push(dependency);
+                }
+            }
+        }
+    }
+
+    Ok(unresolved)
+}
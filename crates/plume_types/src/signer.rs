@@ -1,19 +1,38 @@
 use futures::future::try_join_all;
 use plist::Value;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::fs;
+use tokio::sync::Mutex;
 
 use plume_core::{
     CertificateIdentity, MobileProvision, SettingsScope, SigningSettings, UnifiedSigner,
     developer::DeveloperSession,
 };
+use plume_store::RegisteredAppId;
 
-use crate::{Bundle, BundleType, Error, PlistInfoTrait, SignerApp, SignerMode, SignerOptions};
+use crate::{
+    Bundle, BundleType, DebControl, DebRepository, Error, PlistInfoTrait, RemovableBundleKind,
+    SignerApp, SignerBundleRemoval, SignerMode, SignerOptions, SkippedDylib, ThinningReport,
+    UnresolvedDependency, UnsupportedPreference,
+};
 
 pub struct Signer {
-    certificate: Option<CertificateIdentity>,
+    pub(crate) certificate: Option<CertificateIdentity>,
     pub options: SignerOptions,
     pub provisioning_files: Vec<MobileProvision>,
+    /// App IDs newly created by the last `register_bundle` call.
+    pub registered_app_ids: Vec<RegisteredAppId>,
+    /// Tweak dylibs skipped by their filter in the last `modify_bundle` call.
+    pub skipped_dylibs: Vec<SkippedDylib>,
+    /// Libraries tweaks link against that aren't bundled, from the last `modify_bundle` call.
+    pub unresolved_dependencies: Vec<UnresolvedDependency>,
+    /// Tweak `Depends` the repository couldn't satisfy in the last `modify_bundle` call.
+    pub missing_packages: Vec<String>,
+    /// Tweak settings that couldn't be added to the app in the last `modify_bundle` call.
+    pub unsupported_preferences: Vec<UnsupportedPreference>,
+    /// What thinning removed in the last `modify_bundle` call.
+    pub thinning: Option<ThinningReport>,
 }
 
 impl Signer {
@@ -22,6 +41,12 @@ impl Signer {
             certificate,
             options,
             provisioning_files: Vec::new(),
+            registered_app_ids: Vec::new(),
+            skipped_dylibs: Vec::new(),
+            unresolved_dependencies: Vec::new(),
+            missing_packages: Vec::new(),
+            unsupported_preferences: Vec::new(),
+            thinning: None,
         }
     }
 
@@ -34,6 +59,9 @@ impl Signer {
             return Ok(());
         }
 
+        // Removed before anything else so they never get an App ID registered
+        Self::remove_nested_bundles(bundle, &self.options.removed_bundles).await?;
+
         let bundles = bundle
             .collect_bundles_sorted()?
             .into_iter()
@@ -42,6 +70,13 @@ impl Signer {
 
         if let Some(new_name) = self.options.custom_name.as_ref() {
             bundle.set_name(new_name)?;
+
+            for embedded_bundle in bundles
+                .iter()
+                .filter(|b| *b.bundle_type() == BundleType::AppExtension)
+            {
+                embedded_bundle.set_localized_name(new_name)?;
+            }
         }
 
         if let Some(new_version) = self.options.custom_version.as_ref() {
@@ -69,6 +104,21 @@ impl Signer {
             bundle.set_info_plist_key("CADisableMinimumFrameDurationOnPhone", true)?;
         }
 
+        let scrubbed_keys = self.options.features.info_plist_keys_to_remove();
+
+        if !scrubbed_keys.is_empty() {
+            // Dylibs are collected as pseudo-bundles without an Info.plist
+            for embedded_bundle in bundle
+                .collect_bundles_sorted()?
+                .iter()
+                .filter(|b| *b.bundle_type() != BundleType::Dylib)
+            {
+                for key in &scrubbed_keys {
+                    embedded_bundle.remove_info_plist_key(key)?;
+                }
+            }
+        }
+
         let identifier = bundle.get_bundle_identifier();
 
         if self.options.mode != SignerMode::Adhoc && self.options.custom_identifier.is_none() {
@@ -78,9 +128,9 @@ impl Signer {
         }
 
         if let Some(new_identifier) = self.options.custom_identifier.as_ref() {
-            if let Some(orig_identifier) = identifier {
+            if let Some(orig_identifier) = &identifier {
                 for embedded_bundle in &bundles {
-                    embedded_bundle.set_matching_identifier(&orig_identifier, new_identifier)?;
+                    embedded_bundle.set_matching_identifier(orig_identifier, new_identifier)?;
                 }
             }
         }
@@ -126,15 +176,93 @@ impl Signer {
 
         let has_tweaks = self.options.tweaks.as_ref().is_some_and(|t| !t.is_empty());
 
-        if self.options.features.support_ellekit || has_tweaks {
-            crate::Tweak::install_ellekit(&bundle).await?;
-        }
+        let runtime = if self.options.features.support_ellekit || has_tweaks {
+            Some(
+                crate::Tweak::install_runtime(
+                    bundle,
+                    &self.options.hook_runtime,
+                    self.options.extract_limits,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        self.skipped_dylibs.clear();
+        self.unresolved_dependencies.clear();
+        self.missing_packages.clear();
+        self.unsupported_preferences.clear();
+        self.thinning = None;
 
         if let Some(tweak_files) = self.options.tweaks.as_ref() {
+            let mut installed_binaries = Vec::new();
+            let mut installed_debs = HashSet::new();
+
+            let repository = match self.options.tweak_repository.as_ref() {
+                Some(path) => Some(DebRepository::open(path)?),
+                None => None,
+            };
+
             for tweak_file in tweak_files {
-                let tweak = crate::Tweak::new(tweak_file, bundle).await?;
-                tweak.apply().await?;
+                let mut paths = Vec::new();
+
+                // Dependencies go in first, each only once across all tweaks
+                if let Some(repository) = &repository {
+                    if tweak_file.path.extension().is_some_and(|e| e == "deb") {
+                        let control = DebControl::from_deb(&tweak_file.path)?;
+                        let resolution = repository.resolve(&control);
+
+                        self.missing_packages.extend(
+                            resolution
+                                .missing
+                                .into_iter()
+                                .map(|dep| format!("{}: {}", control.package, dep)),
+                        );
+                        paths.extend(
+                            resolution
+                                .install
+                                .into_iter()
+                                .filter(|path| installed_debs.insert(path.clone())),
+                        );
+                    }
+                }
+
+                if installed_debs.insert(tweak_file.path.clone()) {
+                    paths.push(tweak_file.path.clone());
+                }
+
+                for path in paths {
+                    log::info!("Installing {}", path.display());
+                    let mut tweak = crate::Tweak::new_with_options(
+                        &path,
+                        bundle,
+                        &tweak_file.target,
+                        self.options.extract_limits,
+                    )
+                    .await?;
+                    tweak.set_weak(tweak_file.weak);
+                    // Filters name the identifier the app shipped with
+                    if let (Some(original), Some(renamed)) =
+                        (&identifier, &self.options.custom_identifier)
+                    {
+                        tweak.set_original_identifier(original, renamed);
+                    }
+                    tweak.apply().await?;
+                    self.skipped_dylibs.extend(tweak.skipped_dylibs());
+                    self.unsupported_preferences
+                        .extend(tweak.unsupported_preferences());
+                    installed_binaries.extend(tweak.installed_binaries());
+                }
             }
+
+            // After every tweak is in place, they may link against each other
+            self.unresolved_dependencies = crate::rewrite::rewrite_dependencies(
+                bundle.bundle_dir(),
+                &installed_binaries,
+                &self.options.path_rewrites,
+                runtime.as_deref(),
+            )?;
         }
 
         if self.options.features.support_liquid_glass {
@@ -153,6 +281,46 @@ impl Signer {
             macho.replace_sdk_version("26.0.0")?;
         }
 
+        // Last, tweaks and the runtime may bring slices of their own
+        let architecture = self.options.thin_architecture.as_deref();
+        let strip_symbols = self.options.features.strip_symbols;
+        if architecture.is_some() || strip_symbols {
+            let report =
+                crate::thinning::thin_bundle(bundle.bundle_dir(), architecture, strip_symbols)?;
+            log::info!("{}", report);
+            self.thinning = Some(report);
+        }
+
+        Ok(())
+    }
+
+    async fn remove_nested_bundles(
+        bundle: &Bundle,
+        removal: &SignerBundleRemoval,
+    ) -> Result<(), Error> {
+        if *removal == SignerBundleRemoval::Keep {
+            return Ok(());
+        }
+
+        for removable in bundle.collect_removable_bundles()? {
+            if !removal.should_remove(&removable.path) {
+                continue;
+            }
+
+            log::info!("Removing {}: {}", removable.kind, removable.path);
+            fs::remove_dir_all(bundle.bundle_dir().join(&removable.path)).await?;
+        }
+
+        // Drop containers left empty so they don't end up in the signature
+        for kind in RemovableBundleKind::ALL {
+            for directory in kind.directories() {
+                let dir = bundle.bundle_dir().join(directory);
+                if dir.is_dir() && std::fs::read_dir(&dir)?.next().is_none() {
+                    fs::remove_dir(&dir).await?;
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -177,12 +345,15 @@ impl Signer {
         let bundle_arc = Arc::new(bundle.clone());
         let session_arc = Arc::new(session);
         let team_id_arc = Arc::new(team_id.clone());
+        // Filled as soon as an App ID exists, a later failure doesn't undo its creation
+        let created_app_ids = Arc::new(Mutex::new(Vec::new()));
 
         let futures = bundles.iter().filter_map(|sub_bundle| {
             let sub_bundle = sub_bundle.clone();
             let bundle = bundle_arc.clone();
             let session = session_arc.clone();
             let team_id = team_id_arc.clone();
+            let created_app_ids = created_app_ids.clone();
             let signer_settings = signer_settings.clone();
 
             if signer_settings.embedding.single_profile
@@ -210,12 +381,21 @@ impl Signer {
 
                 let name = sub_bundle.get_bundle_name().unwrap_or_else(|| id.clone());
 
-                session.qh_ensure_app_id(&team_id, &name, &id).await?;
-
-                let app_id_id = session
-                    .qh_get_app_id(&team_id, &id)
-                    .await?
-                    .ok_or_else(|| Error::Other("Failed to get ensured app ID.".into()))?;
+                let (app_id_id, is_new) = match session.qh_get_app_id(&team_id, &id).await? {
+                    Some(app_id) => (app_id, false),
+                    None => (
+                        session.qh_add_app_id(&team_id, &name, &id).await?.app_id,
+                        true,
+                    ),
+                };
+
+                if is_new {
+                    created_app_ids.lock().await.push(RegisteredAppId::new(
+                        team_id.to_string(),
+                        app_id_id.app_id_id.clone(),
+                        id.clone(),
+                    ));
+                }
 
                 if let Some(e) = macho.entitlements().as_ref() {
                     session
@@ -273,8 +453,9 @@ impl Signer {
             })
         });
 
-        let provisionings: Vec<MobileProvision> = try_join_all(futures).await?;
-        self.provisioning_files = provisionings;
+        let provisionings = try_join_all(futures).await;
+        self.registered_app_ids = std::mem::take(&mut *created_app_ids.lock().await);
+        self.provisioning_files = provisionings?;
 
         Ok(())
     }
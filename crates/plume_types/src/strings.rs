@@ -0,0 +1,280 @@
+use std::{fs, path::Path};
+
+use plist::{Dictionary, Value};
+
+use crate::Error;
+
+/// On-disk encoding of a `.strings` file, kept so rewrites don't change it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StringsFormat {
+    Xml,
+    Binary,
+    Utf8 { bom: bool },
+    Utf16Le,
+    Utf16Be,
+}
+
+/// A `.strings` file such as `en.lproj/InfoPlist.strings`.
+///
+/// Xcode may emit these as XML or binary plists, or as the classic
+/// `"key" = "value";` text format (usually UTF-16).
+pub(crate) struct StringsFile {
+    format: StringsFormat,
+    entries: Vec<(String, String)>,
+}
+
+impl StringsFile {
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        if data.starts_with(b"bplist") {
+            let value = Value::from_reader(std::io::Cursor::new(data))?;
+            return Ok(Self::from_dictionary(StringsFormat::Binary, value));
+        }
+
+        let (format, text) = if let Some(rest) = data.strip_prefix(&[0xFF, 0xFE]) {
+            (
+                StringsFormat::Utf16Le,
+                decode_utf16(rest, u16::from_le_bytes)?,
+            )
+        } else if let Some(rest) = data.strip_prefix(&[0xFE, 0xFF]) {
+            (
+                StringsFormat::Utf16Be,
+                decode_utf16(rest, u16::from_be_bytes)?,
+            )
+        } else if let Some(rest) = data.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+            (StringsFormat::Utf8 { bom: true }, decode_utf8(rest)?)
+        } else {
+            (StringsFormat::Utf8 { bom: false }, decode_utf8(data)?)
+        };
+
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("<?xml") || trimmed.starts_with("<plist") {
+            let value = Value::from_reader_xml(text.as_bytes())?;
+            return Ok(Self::from_dictionary(StringsFormat::Xml, value));
+        }
+
+        Ok(Self {
+            format,
+            entries: parse_strings_text(&text)?,
+        })
+    }
+
+    fn from_dictionary(format: StringsFormat, value: Value) -> Self {
+        let entries = value
+            .into_dictionary()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(k, v)| v.into_string().map(|v| (k, v)))
+            .collect();
+
+        Self { format, entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Replaces the value of an existing key, returns whether anything changed.
+    pub fn replace(&mut self, key: &str, value: &str) -> bool {
+        let mut did_change = false;
+        for (k, v) in self.entries.iter_mut() {
+            if k == key && v != value {
+                *v = value.to_string();
+                did_change = true;
+            }
+        }
+        did_change
+    }
+
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::new();
+
+        match self.format {
+            StringsFormat::Xml => self.to_value().to_writer_xml(&mut buf)?,
+            StringsFormat::Binary => self.to_value().to_writer_binary(&mut buf)?,
+            StringsFormat::Utf8 { bom } => {
+                if bom {
+                    buf.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+                }
+                buf.extend_from_slice(self.to_text().as_bytes());
+            }
+            StringsFormat::Utf16Le => {
+                buf.extend_from_slice(&[0xFF, 0xFE]);
+                for unit in self.to_text().encode_utf16() {
+                    buf.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            StringsFormat::Utf16Be => {
+                buf.extend_from_slice(&[0xFE, 0xFF]);
+                for unit in self.to_text().encode_utf16() {
+                    buf.extend_from_slice(&unit.to_be_bytes());
+                }
+            }
+        }
+
+        Ok(buf)
+    }
+
+    fn to_value(&self) -> Value {
+        let mut dict = Dictionary::new();
+        for (k, v) in &self.entries {
+            dict.insert(k.clone(), Value::String(v.clone()));
+        }
+        Value::Dictionary(dict)
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (k, v) in &self.entries {
+            text.push_str(&format!("\"{}\" = \"{}\";\n", escape(k), escape(v)));
+        }
+        text
+    }
+}
+
+fn decode_utf8(data: &[u8]) -> Result<String, Error> {
+    String::from_utf8(data.to_vec())
+        .map_err(|_| Error::Other("Invalid UTF-8 in .strings file".into()))
+}
+
+fn decode_utf16(data: &[u8], from_bytes: fn([u8; 2]) -> u16) -> Result<String, Error> {
+    let units = data
+        .chunks_exact(2)
+        .map(|c| from_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+
+    String::from_utf16(&units).map_err(|_| Error::Other("Invalid UTF-16 in .strings file".into()))
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn parse_strings_text(text: &str) -> Result<Vec<(String, String)>, Error> {
+    let invalid = || Error::Other("Malformed .strings file".into());
+    let mut chars = text.chars().peekable();
+    let mut entries = Vec::new();
+
+    // Skips whitespace and both comment styles
+    fn skip_trivia(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+        loop {
+            match chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    chars.next();
+                }
+                Some('/') => {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    match lookahead.peek() {
+                        Some('/') => {
+                            for c in chars.by_ref() {
+                                if c == '\n' {
+                                    break;
+                                }
+                            }
+                        }
+                        Some('*') => {
+                            chars.next();
+                            chars.next();
+                            let mut prev = '\0';
+                            for c in chars.by_ref() {
+                                if prev == '*' && c == '/' {
+                                    break;
+                                }
+                                prev = c;
+                            }
+                        }
+                        _ => return,
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn read_token(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<String> {
+        let mut out = String::new();
+
+        if chars.peek() != Some(&'"') {
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '$' | ':') {
+                    out.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            return (!out.is_empty()).then_some(out);
+        }
+
+        chars.next();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(out),
+                '\\' => match chars.next()? {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    'U' | 'u' => {
+                        let hex: String = (0..4).filter_map(|_| chars.next()).collect();
+                        let code = u32::from_str_radix(&hex, 16).ok()?;
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    other => out.push(other),
+                },
+                _ => out.push(c),
+            }
+        }
+
+        None
+    }
+
+    loop {
+        skip_trivia(&mut chars);
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let key = read_token(&mut chars).ok_or_else(invalid)?;
+        skip_trivia(&mut chars);
+
+        // `"key";` is shorthand for `"key" = "key";`
+        let value = if chars.peek() == Some(&'=') {
+            chars.next();
+            skip_trivia(&mut chars);
+            read_token(&mut chars).ok_or_else(invalid)?
+        } else {
+            key.clone()
+        };
+
+        skip_trivia(&mut chars);
+        if chars.next() != Some(';') {
+            return Err(invalid());
+        }
+
+        entries.push((key, value));
+    }
+
+    Ok(entries)
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::Path;
+
+use plume_core::MachO;
+use serde::Serialize;
+
+use crate::bundle::is_macho_magic;
+use crate::{Error, relative_path};
+
+// Debug and App Store processing leftovers next to an IPA's `Payload/`,
+// inside an app the same names may be regular resources
+const SYMBOL_DIRECTORIES: &[&str] = &["SwiftSupport", "Symbols", "BCSymbolMaps"];
+
+/// What thinning removed from a bundle.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ThinningReport {
+    /// The device architecture binaries were thinned to.
+    pub architecture: Option<String>,
+    /// Binaries that had slices removed, relative to the app.
+    pub thinned: Vec<String>,
+    /// Symbol files removed, relative to the app, and folders removed from the
+    /// root of the IPA.
+    pub removed: Vec<String>,
+    pub bytes_saved: u64,
+}
+
+impl std::fmt::Display for ThinningReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Saved {:.1} MB",
+            self.bytes_saved as f64 / (1024.0 * 1024.0)
+        )?;
+        if let Some(architecture) = &self.architecture {
+            write!(
+                f,
+                ", thinned {} binaries to {}",
+                self.thinned.len(),
+                architecture
+            )?;
+        }
+        if !self.removed.is_empty() {
+            write!(f, ", removed {} symbol files", self.removed.len())?;
+        }
+        Ok(())
+    }
+}
+
+/// Slices a CPU can run.
+fn compatible_architectures(cpu_architecture: &str) -> Vec<&str> {
+    match cpu_architecture {
+        // Third-party arm64e is still a preview ABI, keep the arm64 fallback
+        "arm64e" => vec!["arm64e", "arm64"],
+        "armv7s" => vec!["armv7s", "armv7"],
+        other => vec![other],
+    }
+}
+
+/// Removes slices `architecture` can't run from every Mach-O in the bundle
+/// and, with `strip_symbols`, its `.bcsymbolmap` files and the symbol folders
+/// of the IPA it was extracted from.
+pub(crate) fn thin_bundle(
+    app_bundle: &Path,
+    architecture: Option<&str>,
+    strip_symbols: bool,
+) -> Result<ThinningReport, Error> {
+    let mut report = ThinningReport {
+        architecture: architecture.map(String::from),
+        ..Default::default()
+    };
+
+    // An extracted IPA, the archive leaves these out but they're still saved
+    let package_root = app_bundle
+        .parent()
+        .filter(|p| p.ends_with("Payload"))
+        .and_then(Path::parent);
+    if let (true, Some(package_root)) = (strip_symbols, package_root) {
+        for name in SYMBOL_DIRECTORIES {
+            let path = package_root.join(name);
+            if path.is_dir() {
+                report.bytes_saved += disk_size(&path)?;
+                fs::remove_dir_all(&path)?;
+                report.removed.push(name.to_string());
+            }
+        }
+    }
+
+    let mut pending = vec![app_bundle.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_symlink() {
+                continue;
+            }
+
+            let is_symbols = file_type.is_file()
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".bcsymbolmap");
+
+            if strip_symbols && is_symbols {
+                report.bytes_saved += entry.metadata()?.len();
+                fs::remove_file(&path)?;
+                report.removed.push(relative_path(app_bundle, &path));
+            } else if file_type.is_dir() {
+                pending.push(path);
+            } else if let Some(architecture) = architecture {
+                if is_macho(&path)? {
+                    let before = entry.metadata()?.len();
+                    if thin_binary(&path, architecture)? {
+                        report.bytes_saved += before.saturating_sub(fs::metadata(&path)?.len());
+                        report.thinned.push(relative_path(app_bundle, &path));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+// Whether any slice was removed
+fn thin_binary(path: &Path, architecture: &str) -> Result<bool, Error> {
+    // Anything with a fat magic that isn't a Mach-O, e.g. a Java class
+    let Ok(mut macho) = MachO::new(path) else {
+        return Ok(false);
+    };
+    let architectures = macho.architectures();
+    let compatible = compatible_architectures(architecture);
+
+    if !architectures
+        .iter()
+        .any(|a| compatible.contains(&a.as_str()))
+    {
+        log::warn!(
+            "{} has no slice for {}, leaving it as is",
+            path.display(),
+            architecture
+        );
+        return Ok(false);
+    }
+
+    let removed = architectures
+        .iter()
+        .map(String::as_str)
+        .filter(|a| !compatible.contains(a))
+        .collect::<Vec<_>>();
+
+    if removed.is_empty() {
+        return Ok(false);
+    }
+
+    log::info!("Removing {} from {}", removed.join(", "), path.display());
+    macho.remove_slices(&removed)?;
+
+    Ok(true)
+}
+
+fn is_macho(path: &Path) -> Result<bool, Error> {
+    use std::io::Read;
+
+    let mut magic = [0u8; 4];
+    let mut file = fs::File::open(path)?;
+    if file.read_exact(&mut magic).is_err() {
+        return Ok(false);
+    }
+
+    Ok(is_macho_magic(magic))
+}
+
+fn disk_size(path: &Path) -> Result<u64, Error> {
+    let metadata = fs::symlink_metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += disk_size(&entry?.path())?;
+    }
+
+    Ok(size)
+}
@@ -1,39 +1,347 @@
 use std::{
     env,
     path::{Path, PathBuf},
+    sync::Mutex,
 };
 
-use plume_core::MachO;
+use plume_core::{DylibLoadCommand, MachO, MachOExt};
+use serde::Serialize;
 use uuid::Uuid;
 
-use crate::{Bundle, Error, PlistInfoTrait, copy_dir_recursively};
+use crate::extract::extract_deb;
+use crate::filter::{FilterTarget, SkippedDylib, SubstrateFilter};
+use crate::inspect::{TweakInspection, inspect_package};
+use crate::preferences::{UnsupportedPreference, has_preferences, install_preferences};
+use crate::{
+    Bundle, BundleType, DebControl, Error, ExtractLimits, PlistInfoTrait, SignerHookRuntime,
+    SignerPathRewrite, SignerTweakTarget, copy_dir_recursively,
+};
 
 const ELLEKIT_BYTES: &[u8] = include_bytes!("./ellekit.deb");
 
+// Where substrate-compatible runtimes put their library, rootful or under `var/jb`
+const RUNTIME_PATHS: &[&str] = &[
+    "Library/Frameworks/CydiaSubstrate.framework",
+    "usr/lib/libellekit.dylib",
+    "usr/lib/ellekit/libellekit.dylib",
+    "usr/lib/libsubstrate.dylib",
+    "usr/lib/libsubstitute.dylib",
+];
+
 pub struct Tweak {
     path: PathBuf,
     app_bundle: PathBuf,
     stage_dir: PathBuf,
+    limits: ExtractLimits,
+    targets: Vec<InjectionTarget>,
+    skipped: Mutex<Vec<SkippedDylib>>,
+    unsupported_preferences: Mutex<Vec<UnsupportedPreference>>,
+    installed: Mutex<Vec<PathBuf>>,
+    weak: bool,
+}
+
+/// A library plume injected into an app.
+#[derive(Debug, Clone, Serialize)]
+pub struct InjectedTweak {
+    /// Load command path, e.g. `@rpath/Tweak.dylib`.
+    pub load_path: String,
+    /// Bundles loading it, relative to the app. Empty for the main app.
+    pub loaded_by: Vec<String>,
+    /// The library or framework in `Frameworks/`, relative to the app.
+    pub file: Option<String>,
+    /// Loaded with `LC_LOAD_WEAK_DYLIB`, the app still launches without it.
+    pub weak: bool,
+}
+
+impl InjectedTweak {
+    fn matches(&self, name: &str) -> bool {
+        self.load_path == name
+            || self.load_path.rsplit('/').next() == Some(name)
+            || self.file.as_deref() == Some(name)
+            || self.file.as_deref().and_then(|f| f.rsplit('/').next()) == Some(name)
+    }
+}
+
+// `@rpath/Tweak.dylib` and `@rpath/Tweak.framework/Tweak` live in `Frameworks/`
+fn injected_file(app_bundle: &Bundle, load_path: &str) -> Option<String> {
+    let name = load_path.strip_prefix("@rpath/")?.split('/').next()?;
+    let file = format!("Frameworks/{}", name);
+
+    app_bundle.bundle_dir().join(&file).exists().then_some(file)
+}
+
+// The first runtime library an extracted package installs, rootful or under `var/jb`
+fn find_runtime(root: &Path) -> Result<Option<PathBuf>, Error> {
+    Ok(["", "var/jb/"]
+        .iter()
+        .flat_map(|prefix| RUNTIME_PATHS.iter().map(move |p| root.join(prefix).join(p)))
+        .find(|p| p.exists())
+        // Versioned ElleKit links `libsubstrate.dylib` to the real library
+        .map(|p| if p.is_dir() { Ok(p) } else { p.canonicalize() })
+        .transpose()?)
+}
+
+// Legacy commands can't be told apart from the app's own weak frameworks,
+// so those only count when the file isn't a framework with an Info.plist
+fn is_injected_command(app_bundle: &Bundle, command: &DylibLoadCommand) -> bool {
+    if command.is_injected() {
+        return true;
+    }
+
+    let app_framework = injected_file(app_bundle, &command.path).is_some_and(|file| {
+        file.ends_with(".framework")
+            && app_bundle
+                .bundle_dir()
+                .join(file)
+                .join("Info.plist")
+                .exists()
+    });
+
+    command.is_legacy_injected() && !app_framework
+}
+
+// Where packages install what we can put in an app, rootful and rootless
+const SEARCH_PATHS: &[&str] = &[
+    "Library/MobileSubstrate/DynamicLibraries",
+    "usr/lib",
+    "Library/Frameworks",
+    "Library/Application Support",
+    "Library/PreferenceBundles",
+    "var/jb/Library/MobileSubstrate/DynamicLibraries",
+    "var/jb/usr/lib",
+    "var/jb/Library/Frameworks",
+    "var/jb/Library/Application Support",
+    "var/jb/Library/PreferenceBundles",
+];
+
+/// Something a tweak installs into an app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TweakItemKind {
+    Dylib,
+    Framework,
+    Bundle,
+    Appex,
+}
+
+impl TweakItemKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.rsplit_once('.')?.1 {
+            "dylib" => Some(TweakItemKind::Dylib),
+            "framework" => Some(TweakItemKind::Framework),
+            "bundle" => Some(TweakItemKind::Bundle),
+            "appex" => Some(TweakItemKind::Appex),
+            _ => None,
+        }
+    }
+
+    /// Where an item named `name` ends up, relative to the app.
+    pub fn destination(self, name: &str) -> String {
+        match self {
+            TweakItemKind::Dylib | TweakItemKind::Framework => format!("Frameworks/{}", name),
+            TweakItemKind::Bundle => name.to_string(),
+            TweakItemKind::Appex => format!("PlugIns/{}", name),
+        }
+    }
+}
+
+// Everything installable in an extracted package, links are skipped
+pub(crate) fn collect_items(root: &Path) -> Result<Vec<(TweakItemKind, PathBuf)>, Error> {
+    fn collect_recursive(
+        dir: &Path,
+        items: &mut Vec<(TweakItemKind, PathBuf)>,
+    ) -> Result<(), Error> {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_symlink() {
+                continue;
+            }
+
+            let kind = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(TweakItemKind::from_name);
+
+            match kind {
+                Some(TweakItemKind::Dylib) if path.is_file() => {
+                    items.push((TweakItemKind::Dylib, path))
+                }
+                Some(kind) if kind != TweakItemKind::Dylib && path.is_dir() => {
+                    items.push((kind, path))
+                }
+                _ if path.is_dir() => collect_recursive(&path, items)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut items = Vec::new();
+    for search_path in SEARCH_PATHS {
+        let dir = root.join(search_path);
+        if dir.exists() {
+            collect_recursive(&dir, &mut items)?;
+        }
+    }
+
+    Ok(items)
+}
+
+/// An executable tweaks are loaded into.
+struct InjectionTarget {
+    executable: PathBuf,
+    /// Reaches the app's `Frameworks` from the executable's location.
+    rpath: String,
+    filter: FilterTarget,
 }
 
 impl Tweak {
     pub async fn install_ellekit(app_bundle: &Bundle) -> Result<(), Error> {
-        let stage_dir = env::temp_dir().join(format!("plume_ellekit_{}", Uuid::new_v4()));
+        Self::install_runtime(
+            app_bundle,
+            &SignerHookRuntime::BundledElleKit,
+            ExtractLimits::default(),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Installs the hooking runtime into the app, returning the path tweaks load it by.
+    pub async fn install_runtime(
+        app_bundle: &Bundle,
+        runtime: &SignerHookRuntime,
+        limits: ExtractLimits,
+    ) -> Result<String, Error> {
+        let stage_dir = env::temp_dir().join(format!("plume_runtime_{}", Uuid::new_v4()));
         tokio::fs::create_dir_all(&stage_dir).await?;
 
-        let deb_path = stage_dir.join("ellekit.deb");
-        tokio::fs::write(&deb_path, ELLEKIT_BYTES).await?;
+        let runtime_path = match runtime {
+            SignerHookRuntime::BundledElleKit => {
+                let deb_path = stage_dir.join("ellekit.deb");
+                tokio::fs::write(&deb_path, ELLEKIT_BYTES).await?;
+                deb_path
+            }
+            SignerHookRuntime::Custom(path) => path.clone(),
+        };
 
-        let tweak = Tweak::new(&deb_path, app_bundle).await?;
-        tweak.install_deb().await?;
+        let result = async {
+            let tweak = Tweak::new_with_options(
+                &runtime_path,
+                app_bundle,
+                &SignerTweakTarget::Main,
+                limits,
+            )
+            .await?;
+            tweak.apply_runtime().await
+        }
+        .await;
 
         tokio::fs::remove_dir_all(&stage_dir).await.ok();
 
-        Ok(())
+        result
+    }
+
+    /// The path tweaks load `runtime` by once [`Tweak::install_runtime`] installed it.
+    pub async fn runtime_load_path(
+        runtime: &SignerHookRuntime,
+        limits: ExtractLimits,
+    ) -> Result<String, Error> {
+        let stage_dir = env::temp_dir().join(format!("plume_runtime_{}", Uuid::new_v4()));
+        let runtime = runtime.clone();
+
+        let result = tokio::task::spawn_blocking({
+            let stage_dir = stage_dir.clone();
+            move || {
+                std::fs::create_dir_all(&stage_dir)?;
+                let runtime_path = match runtime {
+                    SignerHookRuntime::BundledElleKit => {
+                        let deb_path = stage_dir.join("ellekit.deb");
+                        std::fs::write(&deb_path, ELLEKIT_BYTES)?;
+                        deb_path
+                    }
+                    SignerHookRuntime::Custom(path) => path,
+                };
+                let not_found = || Error::TweakRuntimeNotFound(runtime_path.display().to_string());
+
+                let library = match runtime_path.extension().and_then(|e| e.to_str()) {
+                    Some("deb") => {
+                        let root = stage_dir.join("root");
+                        extract_deb(&runtime_path, &root, limits)?;
+                        find_runtime(&root)?.ok_or_else(not_found)?
+                    }
+                    Some("dylib" | "framework") => runtime_path.clone(),
+                    _ => return Err(not_found()),
+                };
+                let name = library
+                    .file_name()
+                    .ok_or_else(not_found)?
+                    .to_string_lossy()
+                    .to_string();
+
+                // Installed into `Frameworks/` under the same name
+                match library.is_dir() {
+                    true => {
+                        let executable = Bundle::new(&library)?
+                            .get_executable()
+                            .ok_or_else(not_found)?;
+                        Ok(format!("@rpath/{}/{}", name, executable))
+                    }
+                    false => Ok(format!("@rpath/{}", name)),
+                }
+            }
+        })
+        .await
+        .map_err(|e| {
+            Error::TweakExtractionFailed(format!("Failed to read the hooking runtime: {}", e))
+        })?;
+
+        tokio::fs::remove_dir_all(&stage_dir).await.ok();
+
+        result
     }
 
     pub async fn new<P: AsRef<Path>>(tweak_path: P, app_bundle: &Bundle) -> Result<Self, Error> {
+        Self::new_with_options(
+            tweak_path,
+            app_bundle,
+            &SignerTweakTarget::default(),
+            ExtractLimits::default(),
+        )
+        .await
+    }
+
+    pub async fn new_with_options<P: AsRef<Path>>(
+        tweak_path: P,
+        app_bundle: &Bundle,
+        target: &SignerTweakTarget,
+        limits: ExtractLimits,
+    ) -> Result<Self, Error> {
         let path = tweak_path.as_ref();
+        Self::validate_path(path)?;
+
+        let stage_dir = env::temp_dir().join(format!("plume_tweak_{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&stage_dir).await?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            app_bundle: app_bundle.bundle_dir().clone(),
+            stage_dir,
+            limits,
+            targets: Self::injection_targets(app_bundle, target)?,
+            skipped: Mutex::new(Vec::new()),
+            unsupported_preferences: Mutex::new(Vec::new()),
+            installed: Mutex::new(Vec::new()),
+            weak: true,
+        })
+    }
+
+    /// Whether to inject with `LC_LOAD_WEAK_DYLIB`, the default, or `LC_LOAD_DYLIB`.
+    pub fn set_weak(&mut self, weak: bool) {
+        self.weak = weak;
+    }
+
+    fn validate_path(path: &Path) -> Result<(), Error> {
         if !path.exists() {
             return Err(Error::TweakInvalidPath);
         }
@@ -43,23 +351,60 @@ impl Tweak {
             .and_then(|n| n.to_str())
             .ok_or(Error::TweakInvalidPath)?;
 
-        if !file_name.ends_with(".deb")
-            && !file_name.ends_with(".dylib")
-            && !file_name.ends_with(".framework")
-            && !file_name.ends_with(".bundle")
-            && !file_name.ends_with(".appex")
-        {
+        if !file_name.ends_with(".deb") && TweakItemKind::from_name(file_name).is_none() {
             return Err(Error::UnsupportedFileType(file_name.to_string()));
         }
 
-        let stage_dir = env::temp_dir().join(format!("plume_tweak_{}", Uuid::new_v4()));
-        tokio::fs::create_dir_all(&stage_dir).await?;
+        Ok(())
+    }
 
-        Ok(Self {
-            path: path.to_path_buf(),
-            app_bundle: app_bundle.bundle_dir().clone(),
-            stage_dir,
+    /// Describes what applying a tweak would do, without an app to apply it to.
+    /// `runtime` is the hooking runtime's load path, see [`Tweak::runtime_load_path`].
+    pub async fn inspect<P: AsRef<Path>>(
+        tweak_path: P,
+        rules: &[SignerPathRewrite],
+        runtime: Option<&str>,
+        limits: ExtractLimits,
+    ) -> Result<TweakInspection, Error> {
+        let path = tweak_path.as_ref().to_path_buf();
+        Self::validate_path(&path)?;
+
+        if path.extension().is_none_or(|e| e != "deb") {
+            let kind = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(TweakItemKind::from_name)
+                .ok_or(Error::TweakInvalidPath)?;
+            let root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+            return inspect_package(&path, &root, &[(kind, path.clone())], None, rules, runtime);
+        }
+
+        let stage_dir = env::temp_dir().join(format!("plume_inspect_{}", Uuid::new_v4()));
+        let rules = rules.to_vec();
+        let runtime = runtime.map(String::from);
+
+        let result = tokio::task::spawn_blocking({
+            let stage_dir = stage_dir.clone();
+            move || {
+                extract_deb(&path, &stage_dir, limits)?;
+                let items = collect_items(&stage_dir)?;
+                let control = DebControl::from_deb(&path).ok();
+                inspect_package(
+                    &path,
+                    &stage_dir,
+                    &items,
+                    control,
+                    &rules,
+                    runtime.as_deref(),
+                )
+            }
         })
+        .await
+        .map_err(|e| Error::TweakExtractionFailed(format!("Failed to inspect .deb: {}", e)))?;
+
+        tokio::fs::remove_dir_all(&stage_dir).await.ok();
+
+        result
     }
 
     pub async fn apply(&self) -> Result<(), Error> {
@@ -71,14 +416,8 @@ impl Tweak {
 
         if file_name.ends_with(".deb") {
             self.install_deb().await?;
-        } else if file_name.ends_with(".framework") {
-            self.install_framework(&self.path).await?;
-        } else if file_name.ends_with(".bundle") {
-            self.install_bundle(&self.path).await?;
-        } else if file_name.ends_with(".appex") {
-            self.install_appex(&self.path).await?;
-        } else if file_name.ends_with(".dylib") {
-            self.install_dylib(&self.path).await?;
+        } else if let Some(kind) = TweakItemKind::from_name(file_name) {
+            self.install_item(kind, &self.path).await?;
         }
 
         tokio::fs::remove_dir_all(&self.stage_dir).await.ok();
@@ -86,137 +425,290 @@ impl Tweak {
         Ok(())
     }
 
-    async fn install_deb(&self) -> Result<(), Error> {
-        use decompress::ExtractOpts;
+    // Only the runtime library is installed, the rest of its package is jailbreak plumbing
+    async fn apply_runtime(&self) -> Result<String, Error> {
+        let not_found = || Error::TweakRuntimeNotFound(self.path.display().to_string());
 
-        let extract_dir = self.stage_dir.join("deb_contents");
-        tokio::fs::create_dir_all(&extract_dir).await?;
-
-        let ar_extract_dir = self.stage_dir.join("ar_contents");
-        tokio::fs::create_dir_all(&ar_extract_dir).await?;
-
-        let ar_path_sync = self.path.clone();
-        let ar_extract_dir_sync = ar_extract_dir.clone();
-
-        tokio::task::spawn_blocking(move || {
-            decompress::decompress(
-                &ar_path_sync,
-                &ar_extract_dir_sync,
-                &ExtractOpts {
-                    strip: 0,
-                    detect_content: false,
-                    filter: Box::new(|_: &std::path::Path| true),
-                    map: Box::new(|p| std::borrow::Cow::Borrowed(p)),
-                },
-            )
-        })
-        .await
-        .ok()
-        .and_then(|r| r.ok())
-        .ok_or_else(|| Error::TweakExtractionFailed("Failed to extract .ar archive".to_string()))?;
-
-        for archive_name in [
-            "data.tar.lzma",
-            "data.tar.gz",
-            "data.tar.xz",
-            "data.tar.bz2",
-            "data.tar",
-        ] {
-            let data_path = ar_extract_dir.join(archive_name);
-            if data_path.exists() {
-                let extract_dir_sync = extract_dir.clone();
-                let data_path_sync = data_path.clone();
-
-                tokio::task::spawn_blocking(move || {
-                    decompress::decompress(
-                        &data_path_sync,
-                        &extract_dir_sync,
-                        &ExtractOpts {
-                            strip: 0,
-                            detect_content: false,
-                            filter: Box::new(|_: &std::path::Path| true),
-                            map: Box::new(|p| std::borrow::Cow::Borrowed(p)),
-                        },
-                    )
-                })
-                .await
-                .map_err(|e| {
-                    Error::TweakExtractionFailed(format!("Failed to extract data.tar: {}", e))
-                })?
-                .map_err(|e| {
-                    Error::TweakExtractionFailed(format!("Failed to extract data.tar: {}", e))
-                })?;
-
-                break;
-            }
+        let runtime = match self.path.extension().and_then(|e| e.to_str()) {
+            Some("deb") => find_runtime(&self.extract_deb().await?)?.ok_or_else(not_found)?,
+            Some("dylib" | "framework") => self.path.clone(),
+            _ => return Err(not_found()),
+        };
+
+        if runtime.is_dir() {
+            self.install_framework(&runtime).await?;
+        } else {
+            self.install_dylib(&runtime, &self.targets.iter().collect::<Vec<_>>())
+                .await?;
         }
 
-        self.scan_and_install(&extract_dir).await
+        tokio::fs::remove_dir_all(&self.stage_dir).await.ok();
+
+        let binary = self.installed_binaries().pop().ok_or_else(not_found)?;
+        let relative = binary
+            .strip_prefix(self.app_bundle.join("Frameworks"))
+            .map_err(|_| not_found())?;
+
+        Ok(format!(
+            "@rpath/{}",
+            relative.to_string_lossy().replace('\\', "/")
+        ))
     }
 
-    async fn scan_and_install(&self, root: &Path) -> Result<(), Error> {
-        let search_paths = [
-            "Library/MobileSubstrate/DynamicLibraries",
-            "usr/lib",
-            "Library/Frameworks",
-            "Library/Application Support",
-            "var/jb/Library/MobileSubstrate/DynamicLibraries",
-            "var/jb/usr/lib",
-            "var/jb/Library/Frameworks",
-            "var/jb/Library/Application Support",
-        ];
-
-        for search_path in search_paths {
-            let dir = root.join(search_path);
-            if dir.exists() {
-                self.scan_directory(&dir).await?;
+    /// Lets MobileSubstrate filters match the identifiers bundles had before
+    /// `original` was renamed to `renamed`.
+    pub fn set_original_identifier(&mut self, original: &str, renamed: &str) {
+        for target in &mut self.targets {
+            target.filter.add_original_identifier(original, renamed);
+        }
+    }
+
+    /// Dylibs left out of some or all executables by their MobileSubstrate filter.
+    pub fn skipped_dylibs(&self) -> Vec<SkippedDylib> {
+        self.skipped.lock().unwrap().clone()
+    }
+
+    /// PreferenceLoader entries that couldn't become working `Settings.bundle` panes.
+    pub fn unsupported_preferences(&self) -> Vec<UnsupportedPreference> {
+        self.unsupported_preferences.lock().unwrap().clone()
+    }
+
+    /// Dylibs and framework executables copied into the app.
+    pub fn installed_binaries(&self) -> Vec<PathBuf> {
+        self.installed.lock().unwrap().clone()
+    }
+
+    fn injection_targets(
+        app_bundle: &Bundle,
+        target: &SignerTweakTarget,
+    ) -> Result<Vec<InjectionTarget>, Error> {
+        let mut targets = Vec::new();
+        let mut found = Vec::new();
+
+        let executables = Self::bundle_executables(app_bundle, *target != SignerTweakTarget::Main)?;
+
+        for (relative_name, bundle, executable) in executables {
+            if let SignerTweakTarget::Bundles(selected) = target {
+                if !selected.contains(&relative_name) {
+                    continue;
+                }
             }
+
+            targets.push(InjectionTarget {
+                executable,
+                rpath: format!(
+                    "@executable_path/{}Frameworks",
+                    "../".repeat(Path::new(&relative_name).components().count())
+                ),
+                filter: FilterTarget::from_bundle(&bundle)?,
+            });
+            found.push(relative_name);
         }
 
-        Ok(())
+        if let SignerTweakTarget::Bundles(selected) = target {
+            if let Some(missing) = selected.iter().find(|b| !found.contains(b)) {
+                return Err(Error::TweakTargetNotFound(missing.clone()));
+            }
+        }
+
+        Ok(targets)
     }
 
-    async fn scan_directory(&self, dir: &Path) -> Result<(), Error> {
-        use futures::future::BoxFuture;
+    // The app and optionally its extensions, by path relative to the app
+    fn bundle_executables(
+        app_bundle: &Bundle,
+        extensions: bool,
+    ) -> Result<Vec<(String, Bundle, PathBuf)>, Error> {
+        let mut bundles = vec![app_bundle.clone()];
+        if extensions {
+            bundles.extend(
+                app_bundle
+                    .collect_nested_bundles()?
+                    .into_iter()
+                    .filter(|b| *b.bundle_type() == BundleType::AppExtension),
+            );
+        }
 
-        fn scan_recursive<'a>(tweak: &'a Tweak, dir: &'a Path) -> BoxFuture<'a, Result<(), Error>> {
-            Box::pin(async move {
-                let mut entries = tokio::fs::read_dir(dir).await?;
+        let mut executables = Vec::new();
+
+        for bundle in bundles {
+            let relative_name = bundle
+                .bundle_dir()
+                .strip_prefix(app_bundle.bundle_dir())
+                .unwrap_or(Path::new(""))
+                .to_string_lossy()
+                .replace('\\', "/");
+            let executable = bundle
+                .get_executable()
+                .ok_or(Error::BundleInfoPlistMissing)?;
+            let executable_path = bundle.bundle_dir().join(executable);
+
+            executables.push((relative_name, bundle, executable_path));
+        }
 
-                while let Some(entry) = entries.next_entry().await? {
-                    let path = entry.path();
-                    let metadata = tokio::fs::symlink_metadata(&path).await?;
+        Ok(executables)
+    }
 
-                    if metadata.is_symlink() {
-                        continue;
+    /// Lists the libraries plume injected into the app and its extensions.
+    pub fn list_injected(app_bundle: &Bundle) -> Result<Vec<InjectedTweak>, Error> {
+        let mut injected: Vec<InjectedTweak> = Vec::new();
+
+        for (relative_name, _, executable) in Self::bundle_executables(app_bundle, true)? {
+            let macho = MachO::new(&executable)?;
+
+            // Every slice, one can load a tweak another doesn't
+            let mut commands: Vec<DylibLoadCommand> = Vec::new();
+            for binary in macho.macho_file().iter_macho() {
+                for command in binary.dylib_load_commands()? {
+                    if is_injected_command(app_bundle, &command)
+                        && !commands.iter().any(|c| c.path == command.path)
+                    {
+                        commands.push(command);
                     }
+                }
+            }
 
-                    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                        if path.is_file() && name.ends_with(".dylib") {
-                            tweak.install_dylib(&path).await?;
-                        } else if path.is_dir() {
-                            if name.ends_with(".framework") {
-                                tweak.install_framework(&path).await?;
-                            } else if name.ends_with(".bundle") {
-                                tweak.install_bundle(&path).await?;
-                            } else if name.ends_with(".appex") {
-                                tweak.install_appex(&path).await?;
-                            } else {
-                                // Recursively scan subdirectories
-                                scan_recursive(tweak, &path).await?;
-                            }
-                        }
-                    }
+            for command in commands {
+                match injected.iter_mut().find(|t| t.load_path == command.path) {
+                    Some(tweak) => tweak.loaded_by.push(relative_name.clone()),
+                    None => injected.push(InjectedTweak {
+                        file: injected_file(app_bundle, &command.path),
+                        weak: command.is_weak(),
+                        load_path: command.path,
+                        loaded_by: vec![relative_name.clone()],
+                    }),
                 }
+            }
+        }
 
-                Ok(())
-            })
+        Ok(injected)
+    }
+
+    /// Removes an injected library, by load path or file name, from every
+    /// bundle loading it and deletes its file from `Frameworks/`.
+    pub fn remove_injected(app_bundle: &Bundle, name: &str) -> Result<InjectedTweak, Error> {
+        let tweak = Self::list_injected(app_bundle)?
+            .into_iter()
+            .find(|t| t.matches(name))
+            .ok_or_else(|| Error::TweakNotInjected(name.to_string()))?;
+
+        for (relative_name, _, executable) in Self::bundle_executables(app_bundle, true)? {
+            if tweak.loaded_by.contains(&relative_name) {
+                let mut macho = MachO::new(&executable)?;
+                macho.remove_dylib(&tweak.load_path)?;
+            }
+        }
+
+        if let Some(file) = &tweak.file {
+            let path = app_bundle.bundle_dir().join(file);
+            if path.is_dir() {
+                std::fs::remove_dir_all(&path)?;
+            } else {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(tweak)
+    }
+
+    // Substrate only loads a dylib where the sibling plist's filter matches
+    fn filtered_targets(&self, dylib_path: &Path) -> Result<Vec<&InjectionTarget>, Error> {
+        let Some(filter) = SubstrateFilter::for_dylib(dylib_path)? else {
+            return Ok(self.targets.iter().collect());
+        };
+
+        let mut accepted = Vec::new();
+        let mut reasons = Vec::new();
+
+        for target in &self.targets {
+            match filter.rejects(&target.filter) {
+                Some(reason) => reasons.push(reason),
+                None => accepted.push(target),
+            }
+        }
+
+        if !reasons.is_empty() {
+            let skipped = SkippedDylib {
+                name: dylib_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                reason: reasons.join("; "),
+                partial: !accepted.is_empty(),
+            };
+            log::info!("{}", skipped);
+
+            self.skipped.lock().unwrap().push(skipped);
         }
 
-        scan_recursive(self, dir).await
+        Ok(accepted)
     }
 
-    async fn install_dylib(&self, dylib_path: &Path) -> Result<(), Error> {
+    async fn install_deb(&self) -> Result<(), Error> {
+        let extract_dir = self.extract_deb().await?;
+        self.scan_and_install(&extract_dir).await
+    }
+
+    async fn extract_deb(&self) -> Result<PathBuf, Error> {
+        let extract_dir = self.stage_dir.join("deb_contents");
+
+        let deb_path = self.path.clone();
+        let extract_dir_sync = extract_dir.clone();
+        let limits = self.limits;
+
+        tokio::task::spawn_blocking(move || extract_deb(&deb_path, &extract_dir_sync, limits))
+            .await
+            .map_err(|e| {
+                Error::TweakExtractionFailed(format!("Failed to extract .deb: {}", e))
+            })??;
+
+        Ok(extract_dir)
+    }
+
+    async fn scan_and_install(&self, root: &Path) -> Result<(), Error> {
+        for prefs_root in [root.to_path_buf(), root.join("var/jb")] {
+            if has_preferences(&prefs_root) {
+                let (panes, unsupported) = install_preferences(&prefs_root, &self.app_bundle)?;
+                for pane in panes {
+                    log::info!("Added {} to the app's settings", pane.title);
+                }
+                for preference in &unsupported {
+                    log::warn!("{}", preference);
+                }
+                self.unsupported_preferences
+                    .lock()
+                    .unwrap()
+                    .extend(unsupported);
+            }
+        }
+
+        for (kind, path) in collect_items(root)? {
+            self.install_item(kind, &path).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn install_item(&self, kind: TweakItemKind, path: &Path) -> Result<(), Error> {
+        match kind {
+            TweakItemKind::Dylib => {
+                let targets = self.filtered_targets(path)?;
+                if !targets.is_empty() {
+                    self.install_dylib(path, &targets).await?;
+                }
+                Ok(())
+            }
+            TweakItemKind::Framework => self.install_framework(path).await,
+            TweakItemKind::Bundle => self.install_bundle(path).await,
+            TweakItemKind::Appex => self.install_appex(path).await,
+        }
+    }
+
+    async fn install_dylib(
+        &self,
+        dylib_path: &Path,
+        targets: &[&InjectionTarget],
+    ) -> Result<(), Error> {
         let frameworks_dir = self.app_bundle.join("Frameworks");
         tokio::fs::create_dir_all(&frameworks_dir).await?;
 
@@ -225,8 +717,8 @@ impl Tweak {
 
         tokio::fs::copy(dylib_path, &dest).await?;
 
-        Self::patch_cydiasubstrate(&dest);
-        self.inject_dylib(&dest, false).await
+        self.installed.lock().unwrap().push(dest.clone());
+        self.inject_dylib(&dest, false, targets).await
     }
 
     async fn install_framework(&self, framework_path: &Path) -> Result<(), Error> {
@@ -240,10 +732,20 @@ impl Tweak {
 
         if let Ok(bundle) = Bundle::new(&dest) {
             if let Some(exec_name) = bundle.get_executable() {
-                let exec_path = dest.join(exec_name);
+                let exec_path = dest.join(&exec_name);
+
+                // Executables linked from elsewhere in the package would dangle in the app
+                if exec_path.is_symlink() {
+                    if let Ok(source) = framework_path.join(&exec_name).canonicalize() {
+                        tokio::fs::remove_file(&exec_path).await?;
+                        tokio::fs::copy(&source, &exec_path).await?;
+                    }
+                }
+
                 if exec_path.exists() {
-                    Self::patch_cydiasubstrate(&exec_path);
-                    self.inject_dylib(&exec_path, true).await?;
+                    self.installed.lock().unwrap().push(exec_path.clone());
+                    let targets = self.targets.iter().collect::<Vec<_>>();
+                    self.inject_dylib(&exec_path, true, &targets).await?;
                 }
             }
         }
@@ -268,17 +770,12 @@ impl Tweak {
         copy_dir_recursively(appex_path, &dest).await
     }
 
-    async fn inject_dylib(&self, dylib_path: &Path, is_framework: bool) -> Result<(), Error> {
-        let bundle = Bundle::new(&self.app_bundle)?;
-        let executable_name = bundle
-            .get_executable()
-            .ok_or(Error::BundleInfoPlistMissing)?;
-
-        let executable_path = self.app_bundle.join(&executable_name);
-        if !executable_path.exists() {
-            return Err(Error::BundleInfoPlistMissing);
-        }
-
+    async fn inject_dylib(
+        &self,
+        dylib_path: &Path,
+        is_framework: bool,
+        targets: &[&InjectionTarget],
+    ) -> Result<(), Error> {
         let inject_path = if is_framework {
             let components: Vec<_> = dylib_path.components().rev().take(2).collect();
             format!(
@@ -300,20 +797,28 @@ impl Tweak {
             format!("@rpath/{}", file_name)
         };
 
-        let mut macho = MachO::new(&executable_path)?;
-        macho.add_dylib(&inject_path)?;
-        macho.write_changes()?;
-
-        Ok(())
-    }
+        for target in targets {
+            if !target.executable.exists() {
+                return Err(Error::BundleInfoPlistMissing);
+            }
 
-    fn patch_cydiasubstrate(binary_path: &Path) {
-        if let Ok(mut macho) = MachO::new(binary_path) {
-            let _ = macho.replace_dylib(
-                "/Library/Frameworks/CydiaSubstrate.framework/CydiaSubstrate",
-                "@rpath/CydiaSubstrate.framework/CydiaSubstrate",
-            );
-            let _ = macho.write_changes();
+            // Slices already loading it are left alone
+            let mut macho = MachO::new(&target.executable)?;
+            macho.add_dylib(&inject_path, self.weak)?;
+
+            // `@rpath` only resolves if the executable can reach `Frameworks/`,
+            // reparse as `add_dylib` leaves the parsed load commands behind
+            let mut macho = MachO::new(&target.executable)?;
+            if !macho.rpaths()?.contains(&target.rpath) {
+                log::info!(
+                    "Adding rpath {} to {}",
+                    target.rpath,
+                    target.executable.display()
+                );
+                macho.add_rpath(&target.rpath)?;
+            }
         }
+
+        Ok(())
     }
 }
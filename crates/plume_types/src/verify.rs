@@ -0,0 +1,231 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use plist::Value;
+use plume_core::{MachO, MobileProvision};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{Bundle, BundleType, Error, PlistInfoTrait, relative_path};
+
+// Top level files `rules2` tells codesign to leave out of the seal
+const OMITTED_RESOURCES: &[&str] = &["Info.plist", "PkgInfo"];
+
+/// Signature check result for a single bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleVerification {
+    /// Path relative to the directory containing the verified bundle.
+    pub path: String,
+    pub problems: Vec<String>,
+}
+
+impl BundleVerification {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Bundle {
+    /// Verifies the code signature of this bundle and everything nested in it.
+    pub fn verify(&self) -> Result<Vec<BundleVerification>, Error> {
+        let root = self.bundle_dir().parent().unwrap_or(self.bundle_dir());
+        let mut results = Vec::new();
+
+        for bundle in self.collect_bundles_sorted()? {
+            // Resource bundles aren't code, their files are sealed by the parent
+            if *bundle.bundle_type() == BundleType::Unknown {
+                continue;
+            }
+
+            results.push(BundleVerification {
+                path: relative_path(root, bundle.bundle_dir()),
+                problems: bundle.verify_single()?,
+            });
+        }
+
+        Ok(results)
+    }
+
+    fn verify_single(&self) -> Result<Vec<String>, Error> {
+        if *self.bundle_type() == BundleType::Dylib {
+            return Ok(match MachO::new(self.bundle_dir()) {
+                Ok(macho) => macho.verify_signature()?,
+                Err(e) => vec![e.to_string()],
+            });
+        }
+
+        let Some(executable) = self.get_executable() else {
+            return Ok(vec!["CFBundleExecutable is missing".into()]);
+        };
+
+        let mut problems = Vec::new();
+
+        match MachO::new(self.bundle_dir().join(&executable)) {
+            Ok(macho) => {
+                problems.extend(
+                    macho
+                        .verify_signature()?
+                        .into_iter()
+                        .map(|p| format!("{executable}: {p}")),
+                );
+
+                if self.bundle_type().should_have_entitlements() {
+                    problems.extend(self.verify_entitlements(&macho));
+                }
+            }
+            Err(e) => problems.push(format!("{executable}: {e}")),
+        }
+
+        problems.extend(self.verify_code_resources(&executable)?);
+
+        Ok(problems)
+    }
+
+    fn verify_code_resources(&self, executable: &str) -> Result<Vec<String>, Error> {
+        let resources_path = self.bundle_dir().join("_CodeSignature/CodeResources");
+        if !resources_path.exists() {
+            return Ok(vec!["_CodeSignature/CodeResources is missing".into()]);
+        }
+
+        let resources = Value::from_file(&resources_path)?;
+        let files = resources
+            .as_dictionary()
+            .and_then(|d| d.get("files2"))
+            .and_then(Value::as_dictionary)
+            .cloned()
+            .unwrap_or_default();
+
+        let mut problems = Vec::new();
+
+        // Every sealed file must still match what was signed
+        for (key, entry) in &files {
+            let path = self.bundle_dir().join(key);
+            let entry = entry.as_dictionary();
+            let optional = entry
+                .and_then(|e| e.get("optional"))
+                .and_then(Value::as_boolean)
+                .unwrap_or(false);
+
+            if fs::symlink_metadata(&path).is_err() {
+                if !optional {
+                    problems.push(format!("{key} is sealed but missing"));
+                }
+                continue;
+            }
+
+            if let Some(target) = entry
+                .and_then(|e| e.get("symlink"))
+                .and_then(Value::as_string)
+            {
+                if fs::read_link(&path).ok().as_deref() != Some(Path::new(target)) {
+                    problems.push(format!("{key} no longer links to {target}"));
+                }
+                continue;
+            }
+
+            // Nested code is sealed by its cdhash and verified on its own
+            if path.is_dir() || entry.is_some_and(|e| e.contains_key("cdhash")) {
+                continue;
+            }
+
+            if let Some(hash) = entry.and_then(|e| e.get("hash2")).and_then(Value::as_data) {
+                if Sha256::digest(fs::read(&path)?).as_slice() != hash {
+                    problems.push(format!("{key} was modified after signing"));
+                }
+            }
+        }
+
+        // And everything in the bundle must be sealed
+        let nested_code = self
+            .collect_nested_bundles()?
+            .into_iter()
+            .filter(|b| *b.bundle_type() != BundleType::Unknown)
+            .map(|b| b.bundle_dir().clone())
+            .collect::<Vec<_>>();
+
+        for path in collect_files(self.bundle_dir(), &nested_code)? {
+            let key = relative_path(self.bundle_dir(), &path);
+
+            if key.starts_with("_CodeSignature/")
+                || key == executable
+                || OMITTED_RESOURCES.contains(&key.as_str())
+                || key.ends_with(".DS_Store")
+            {
+                continue;
+            }
+
+            if !files.contains_key(&key) {
+                problems.push(format!("{key} is not sealed by CodeResources"));
+            }
+        }
+
+        Ok(problems)
+    }
+
+    fn verify_entitlements(&self, macho: &MachO) -> Vec<String> {
+        let Some(entitlements) = macho.entitlements() else {
+            return Vec::new();
+        };
+
+        // Ad-hoc signed bundles have nothing to compare against
+        let profile_path = self.bundle_dir().join("embedded.mobileprovision");
+        if !profile_path.exists() {
+            return Vec::new();
+        }
+
+        let profile = match MobileProvision::load_with_path(&profile_path) {
+            Ok(profile) => profile,
+            Err(e) => return vec![format!("embedded.mobileprovision: {e}")],
+        };
+
+        let mut problems = Vec::new();
+
+        for (key, value) in entitlements {
+            match profile.entitlements().get(key) {
+                None => problems.push(format!("entitlement {key} is not in the profile")),
+                Some(allowed) if !is_entitlement_allowed(value, allowed) => {
+                    problems.push(format!("entitlement {key} exceeds what the profile allows"))
+                }
+                _ => {}
+            }
+        }
+
+        problems
+    }
+}
+
+fn is_entitlement_allowed(value: &Value, allowed: &Value) -> bool {
+    match (value, allowed) {
+        (Value::String(v), Value::String(pattern)) => match pattern.strip_suffix('*') {
+            Some(prefix) => v.starts_with(prefix),
+            None => v == pattern,
+        },
+        (Value::Array(values), Value::Array(_)) => {
+            values.iter().all(|v| is_entitlement_allowed(v, allowed))
+        }
+        (value, Value::Array(patterns)) => patterns
+            .iter()
+            .any(|pattern| is_entitlement_allowed(value, pattern)),
+        (Value::Boolean(v), Value::Boolean(a)) => !*v || *a,
+        _ => value == allowed,
+    }
+}
+
+// Files and symlinks, without descending into nested code bundles
+fn collect_files(dir: &Path, nested_code: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if nested_code.contains(&path) {
+            files.push(path);
+        } else if path.is_dir() && !path.is_symlink() {
+            files.extend(collect_files(&path, nested_code)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}